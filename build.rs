@@ -0,0 +1,264 @@
+//! Generates the `models::catalog` structs from the dotted field names in
+//! `constants::CENSYS_SEARCH_FIELDS`.
+
+use std::{collections::BTreeMap, env, fmt::Write, fs, path::Path};
+
+#[allow(dead_code)]
+mod constants {
+    include!("src/constants.rs");
+}
+
+/// Rust types for catalog paths, first match wins. A pattern is either a full
+/// path, `parent.*` for any direct child of `parent`, `*.name` for any path
+/// ending in `.name`, or `*suffix` for any field whose name ends in `suffix`.
+///
+/// On an object, `[]` marks a list, `{}` a single record and `{..}` a list of
+/// `key` and `value` pairs that the API returns as a JSON object. Objects
+/// without a hint accept either a list or a record. Leaves without a hint keep
+/// the raw JSON value.
+const TYPE_HINTS: &[(&str, &str)] = &[
+    ("services", "[]"),
+    ("services.software", "[]"),
+    ("services.tls.certificates.chain", "[]"),
+    ("*.other", "{..}"),
+    ("services.dns.additionals", "[]"),
+    ("services.dns.answers", "[]"),
+    ("services.dns.authorities", "[]"),
+    ("services.dns.questions", "[]"),
+    ("services.*", "{}"),
+    ("*.http_info", "{}"),
+    ("*.headers", "{}"),
+    ("*.request", "{}"),
+    ("*.response", "{}"),
+    ("*_response", "{}"),
+    ("services.ssh.endpoint_id", "{}"),
+    ("services.ssh.server_host_key", "{}"),
+    ("services.tls.certificates", "{}"),
+    ("services.tls.certificates.leaf_data", "{}"),
+    ("operating_system", "{}"),
+    ("location", "{}"),
+    ("location.coordinates", "{}"),
+    ("autonomous_system", "{}"),
+    ("dns", "{}"),
+    ("dns.reverse_dns", "{}"),
+    ("ip", "String"),
+    ("name", "String"),
+    ("location.continent", "String"),
+    ("location.country", "String"),
+    ("location.country_code", "String"),
+    ("location.province", "String"),
+    ("location.city", "String"),
+    ("location.postal_code", "String"),
+    ("location.timezone", "String"),
+    ("location.registered_country", "String"),
+    ("location.registered_country_code", "String"),
+    ("location.coordinates.latitude", "f64"),
+    ("location.coordinates.longitude", "f64"),
+    ("autonomous_system.asn", "u32"),
+    ("autonomous_system.bgp_prefix", "String"),
+    ("autonomous_system.country_code", "String"),
+    ("autonomous_system.description", "String"),
+    ("autonomous_system.name", "String"),
+    ("autonomous_system.organization", "String"),
+    ("services.port", "u16"),
+    ("services.service_name", "String"),
+    ("services.extended_service_name", "String"),
+    ("services.transport_protocol", "String"),
+    ("services.perspective_id", "String"),
+    ("services.source_ip", "String"),
+    ("services.certificate", "String"),
+    ("services.truncated", "bool"),
+    ("services.banner_hex", "String"),
+    ("services.banner_hashes", "Vec<String>"),
+    ("services.http.supports_http2", "bool"),
+    ("services.http.request.method", "String"),
+    ("services.http.request.uri", "String"),
+    ("services.http.response.protocol", "String"),
+    ("services.http.response.status_code", "u16"),
+    ("services.http.response.status_reason", "String"),
+    ("services.http.response.html_title", "String"),
+    ("services.http.response.html_tags", "Vec<String>"),
+    ("services.http.response.body_hash", "String"),
+    ("services.http.response.body_hashes", "Vec<String>"),
+    ("services.http.response.body_size", "u64"),
+    ("services.ssh.endpoint_id.*", "String"),
+    ("services.ssh.server_host_key.fingerprint_sha256", "String"),
+    ("services.tls.version_selected", "String"),
+    ("services.tls.cipher_selected", "String"),
+    ("services.tls.certificates.leaf_fp_sha_256", "String"),
+    ("services.tls.certificates.chain_fps_sha_256", "Vec<String>"),
+    ("services.tls.certificates.leaf_data.pubkey_bit_size", "u32"),
+    ("*.software.part", "String"),
+    ("*.software.vendor", "String"),
+    ("*.software.product", "String"),
+    ("*.software.version", "String"),
+    ("*.software.source", "String"),
+    ("operating_system.part", "String"),
+    ("operating_system.vendor", "String"),
+    ("operating_system.product", "String"),
+    ("operating_system.version", "String"),
+    ("operating_system.source", "String"),
+    ("*.uniform_resource_identifier", "String"),
+    ("*.component_uniform_resource_identifiers", "Vec<String>"),
+    ("*.names", "Vec<String>"),
+    ("*.issuer.common_name", "Vec<String>"),
+    ("*.issuer.country", "Vec<String>"),
+    ("*.issuer.organization", "Vec<String>"),
+    ("*.subject.common_name", "Vec<String>"),
+    ("*.subject.country", "Vec<String>"),
+    ("*.subject.organization", "Vec<String>"),
+    ("*.banner", "String"),
+    ("*_dn", "String"),
+    ("*_at", "String"),
+];
+
+/// Fields every record has, which are not wrapped in an `Option`.
+const REQUIRED_FIELDS: &[&str] = &[
+    "ip",
+    "services.port",
+    "services.service_name",
+    "autonomous_system.asn",
+    "location.coordinates.latitude",
+    "location.coordinates.longitude",
+];
+
+/// Fields in host records that cannot be searched, so are missing from the
+/// catalog.
+const RESPONSE_ONLY_FIELDS: &[&str] = &[
+    "last_updated_at",
+    "location_updated_at",
+    "autonomous_system_updated_at",
+    "services.observed_at",
+];
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "break", "const", "continue", "crate", "do", "else", "enum", "extern", "false", "fn",
+    "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "async", "await", "dyn", "abstract", "become", "box", "final", "macro", "override",
+    "priv", "typeof", "unsized", "virtual", "yield", "try",
+];
+
+#[derive(Default)]
+struct Node {
+    children: BTreeMap<String, Node>,
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/constants.rs");
+
+    let mut root = Node::default();
+    for field in constants::CENSYS_SEARCH_FIELDS
+        .lines()
+        .chain(RESPONSE_ONLY_FIELDS.iter().copied())
+    {
+        let mut node = &mut root;
+        for segment in field.split('.') {
+            node = node.children.entry(segment.to_owned()).or_default();
+        }
+    }
+
+    let mut structs = BTreeMap::new();
+    generate_struct(&root, "", "Host", &mut structs);
+    let code: String = structs.into_values().collect();
+
+    let out_dir = env::var("OUT_DIR").expect("Cargo sets OUT_DIR for build scripts");
+    fs::write(Path::new(&out_dir).join("catalog.rs"), code).expect("Failed to write catalog.rs");
+}
+
+fn generate_struct(node: &Node, path: &str, name: &str, structs: &mut BTreeMap<String, String>) {
+    let mut code = String::new();
+    if path.is_empty() {
+        writeln!(code, "/// A host record with every field in the catalog.").unwrap();
+    } else {
+        writeln!(code, "/// `{}`", path).unwrap();
+    }
+    writeln!(
+        code,
+        "#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]\npub struct {} {{",
+        name
+    )
+    .unwrap();
+    for (segment, child) in &node.children {
+        let child_path = if path.is_empty() {
+            segment.to_owned()
+        } else {
+            format!("{}.{}", path, segment)
+        };
+        let hint = type_hint(&child_path, !child.children.is_empty());
+        let field_type = if child.children.is_empty() {
+            hint.unwrap_or("Value").to_owned()
+        } else if hint == Some("{..}") {
+            "Map<String, Value>".to_owned()
+        } else {
+            let child_name = struct_name(&child_path);
+            generate_struct(child, &child_path, &child_name, structs);
+            match hint {
+                Some("[]") => format!("Vec<{}>", child_name),
+                Some("{}") => child_name,
+                _ => format!("OneOrMany<{}>", child_name),
+            }
+        };
+        let field_name = if RUST_KEYWORDS.contains(&segment.as_str()) {
+            format!("r#{}", segment)
+        } else {
+            segment.to_owned()
+        };
+        if REQUIRED_FIELDS.contains(&child_path.as_str()) {
+            writeln!(code, "    pub {}: {},", field_name, field_type).unwrap();
+        } else {
+            writeln!(
+                code,
+                "    #[serde(skip_serializing_if = \"Option::is_none\")]\n    pub {}: Option<{}>,",
+                field_name, field_type
+            )
+            .unwrap();
+        }
+    }
+    writeln!(
+        code,
+        "    #[serde(flatten)]\n    pub extra: Map<String, Value>,\n}}\n"
+    )
+    .unwrap();
+    let previous = structs.insert(name.to_owned(), code);
+    assert!(
+        previous.is_none(),
+        "Two catalog paths map to struct {}",
+        name
+    );
+}
+
+fn type_hint(path: &str, is_object: bool) -> Option<&'static str> {
+    let field = path.rsplit('.').next().unwrap_or(path);
+    TYPE_HINTS
+        .iter()
+        .filter(|(_, rust_type)| matches!(*rust_type, "[]" | "{}" | "{..}") == is_object)
+        .find(|(pattern, _)| {
+            if let Some(parent) = pattern.strip_suffix(".*") {
+                path.rsplit_once('.')
+                    .is_some_and(|(path_parent, _)| path_parent == parent)
+            } else if let Some(tail) = pattern.strip_prefix('*') {
+                if tail.starts_with('.') {
+                    path.ends_with(tail)
+                } else {
+                    field.ends_with(tail)
+                }
+            } else {
+                path == *pattern
+            }
+        })
+        .map(|(_, rust_type)| *rust_type)
+}
+
+/// `services.tls.certificates` becomes `ServicesTlsCertificates`.
+fn struct_name(path: &str) -> String {
+    path.split(['.', '_'])
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().expect("Words are not empty");
+            first.to_ascii_uppercase().to_string() + chars.as_str()
+        })
+        .collect()
+}
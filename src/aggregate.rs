@@ -0,0 +1,90 @@
+use censys_search::constants;
+use serde_json::Value;
+use std::fmt::Write;
+
+const BAR_WIDTH: usize = 40;
+
+pub fn parse_field(value: &str) -> Result<String, String> {
+    if constants::CENSYS_SEARCH_FIELDS
+        .lines()
+        .any(|field| field == value)
+    {
+        Ok(value.to_owned())
+    } else {
+        Err(format!(
+            "unknown field '{}' (see `censys-search fields`)",
+            value
+        ))
+    }
+}
+
+pub fn render_histogram(result: &Value) -> String {
+    let buckets = sorted_buckets(result);
+    let key_width = buckets.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+    let max_count = buckets.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    let mut histogram = String::new();
+    for (key, count) in &buckets {
+        let bar_length = if max_count == 0 {
+            0
+        } else {
+            // Round up so that every non-empty bucket gets at least one cell.
+            ((*count as u128 * BAR_WIDTH as u128).div_ceil(max_count as u128)) as usize
+        };
+        writeln!(
+            histogram,
+            "{:<key_width$}  {:<BAR_WIDTH$}  {}",
+            key,
+            "█".repeat(bar_length),
+            count
+        )
+        .unwrap();
+    }
+    let omitted = result["total_omitted"].as_u64().unwrap_or(0);
+    write!(
+        histogram,
+        "\n{} hits in {} buckets",
+        result["total"].as_u64().unwrap_or(0),
+        buckets.len()
+    )
+    .unwrap();
+    if omitted > 0 {
+        write!(histogram, " ({} in other buckets)", omitted).unwrap();
+    }
+    histogram
+}
+
+pub fn render_csv(result: &Value) -> String {
+    let mut csv = String::from("key,count");
+    for (key, count) in sorted_buckets(result) {
+        write!(csv, "\n{},{}", escape_csv(&key), count).unwrap();
+    }
+    csv
+}
+
+fn sorted_buckets(result: &Value) -> Vec<(String, u64)> {
+    let mut buckets: Vec<(String, u64)> = result["buckets"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+        .iter()
+        .map(|bucket| {
+            let key = match &bucket["key"] {
+                Value::String(key) => key.to_owned(),
+                key => key.to_string(),
+            };
+            (key, bucket["count"].as_u64().unwrap_or(0))
+        })
+        .collect();
+    buckets.sort_by(|(key_a, count_a), (key_b, count_b)| {
+        count_b.cmp(count_a).then_with(|| key_a.cmp(key_b))
+    });
+    buckets
+}
+
+fn escape_csv(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
@@ -0,0 +1,116 @@
+//! Non-blocking client, enabled with the `async` feature.
+
+use crate::{constants, get_cursor_from_response, paths, Error};
+use futures_util::{stream, Stream, TryStreamExt};
+use reqwest::header::{ACCEPT, AUTHORIZATION};
+use reqwest::{Client, Method};
+use serde_json::Value;
+
+#[derive(Clone)]
+pub struct CensysClient {
+    client: Client,
+    token: String,
+    base_url: String,
+}
+
+impl CensysClient {
+    pub fn new(api_id: &str, secret: &str) -> Self {
+        CensysClient {
+            client: Client::new(),
+            token: base64::encode(format!("{}:{}", api_id, secret)),
+            base_url: constants::BASE_URL.to_owned(),
+        }
+    }
+
+    /// Sends requests to another server, such as a local mock, instead of
+    /// `constants::BASE_URL`.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Sends a request to a path built with the `paths` module.
+    pub async fn send_request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&Value>,
+    ) -> Result<Value, Error> {
+        let mut request = self
+            .client
+            .request(method, format!("{}{}", self.base_url, path))
+            .header(ACCEPT, "application/json")
+            .header(AUTHORIZATION, format!("Basic {}", self.token));
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|err| Error::Transport(err.to_string()))?;
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|err| Error::Transport(err.to_string()))?;
+        let json_response = if text.trim().is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(&text)
+                .map_err(|err| Error::InvalidJson(status.as_u16(), err.to_string()))?
+        };
+        if status.is_success() {
+            Ok(json_response)
+        } else {
+            Err(Error::Api(status.as_u16(), json_response))
+        }
+    }
+
+    pub async fn view_host(&self, ip: &str, at_time: Option<&str>) -> Result<Value, Error> {
+        let path = paths::make_path_from_ip(ip, at_time);
+        let json_response = self.send_request(Method::GET, &path, None).await?;
+        Ok(json_response["result"].to_owned())
+    }
+
+    /// Streams the hits of a host search. Each page is only requested once the
+    /// hits of the previous one have been consumed.
+    pub fn search_hosts<'a>(
+        &'a self,
+        query: &str,
+    ) -> impl Stream<Item = Result<Value, Error>> + 'a {
+        self.search(paths::make_path_from_query(query))
+    }
+
+    /// Streams the hits of a certificate search, see `search_hosts`.
+    pub fn search_certificates<'a>(
+        &'a self,
+        query: &str,
+        fields: &[&str],
+    ) -> impl Stream<Item = Result<Value, Error>> + 'a {
+        self.search(paths::make_path_from_cert_query(query, fields))
+    }
+
+    fn search(&self, path: String) -> impl Stream<Item = Result<Value, Error>> + '_ {
+        // The state is the cursor of the next page, or None once paging is done.
+        stream::try_unfold(Some(None), move |cursor: Option<Option<String>>| {
+            let path = path.clone();
+            async move {
+                let Some(cursor) = cursor else {
+                    return Ok(None);
+                };
+                let page_path = match cursor {
+                    Some(cursor) => paths::append_query_param(&path, "cursor", &cursor),
+                    None => path,
+                };
+                let json_response = self.send_request(Method::GET, &page_path, None).await?;
+                let hits = match json_response["result"]["hits"].to_owned() {
+                    Value::Array(hits) => hits,
+                    _ => return Err(Error::UnexpectedResponse(json_response)),
+                };
+                let next_cursor = get_cursor_from_response(&json_response).map(Some);
+                Ok(Some((stream::iter(hits.into_iter().map(Ok)), next_cursor)))
+            }
+        })
+        .try_flatten()
+    }
+}
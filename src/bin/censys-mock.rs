@@ -0,0 +1,117 @@
+use censys_search::mock::{self, FaultRule, MockServer};
+use clap::{arg, ArgAction, Command};
+use serde_json::Value;
+use std::{fs, process};
+
+fn main() {
+    let arg_matches = build_cli().get_matches();
+
+    let hosts = match arg_matches.get_one::<String>("data") {
+        Some(path) => read_hosts(path).unwrap_or_else(|err| {
+            eprintln!("Unable to read hosts from {}: {}", path, err);
+            process::exit(1);
+        }),
+        None => mock::generate_hosts(
+            *arg_matches
+                .get_one::<u16>("hosts")
+                .expect("Argument has a default value") as usize,
+        ),
+    };
+    let host_count = hosts.len();
+    let mut builder = MockServer::builder().hosts(hosts).page_size(
+        *arg_matches
+            .get_one::<usize>("page_size")
+            .expect("Argument has a default value"),
+    );
+    if let (Some(api_id), Some(secret)) = (
+        arg_matches.get_one::<String>("api_id"),
+        arg_matches.get_one::<String>("secret"),
+    ) {
+        builder = builder.credentials(api_id, secret);
+    }
+    for rule in arg_matches
+        .get_many::<FaultRule>("fault")
+        .into_iter()
+        .flatten()
+    {
+        builder = builder.fault(*rule);
+    }
+    let listen = arg_matches
+        .get_one::<String>("listen")
+        .expect("Argument has a default value");
+    let server = builder.start(listen).unwrap_or_else(|err| {
+        eprintln!("Unable to listen on {}: {}", listen, err);
+        process::exit(1);
+    });
+    eprintln!(
+        "Serving {} hosts, set CENSYS_API_URL to use them",
+        host_count
+    );
+    println!("{}", server.url());
+    server.join();
+}
+
+fn build_cli() -> Command {
+    Command::new("censys-mock")
+        .about("Serve a local mock of the Censys Search API for testing")
+        .arg(
+            arg!(-l --listen <ADDRESS> "Address to listen on (use port 0 for any free port)")
+                .required(false)
+                .default_value("127.0.0.1:8080"),
+        )
+        .arg(
+            arg!(--hosts <N> "Number of hosts to generate")
+                .required(false)
+                .value_parser(
+                    clap::value_parser!(u16).range(0..=mock::MAX_GENERATED_HOSTS as i64),
+                )
+                .default_value("100")
+                .conflicts_with("data"),
+        )
+        .arg(
+            arg!(--data <FILE> "Serve these hosts instead, as a JSON array or one JSON object per line")
+                .required(false),
+        )
+        .arg(
+            arg!(--page_size <N> "Number of search hits per page")
+                .required(false)
+                .value_parser(clap::value_parser!(usize))
+                .default_value("50"),
+        )
+        .arg(
+            arg!(-i --api_id <ID> "Reject requests without this API ID")
+                .required(false)
+                .requires("secret"),
+        )
+        .arg(
+            arg!(-s --secret <SECRET> "Reject requests without this API secret")
+                .required(false)
+                .requires("api_id"),
+        )
+        .arg(
+            arg!(--fault <RULE> "Fail the Nth request with 429, a 5xx status or malformed JSON")
+                .required(false)
+                .action(ArgAction::Append)
+                .value_parser(clap::builder::ValueParser::new(|value: &str| {
+                    value.parse::<FaultRule>()
+                })),
+        )
+        .after_help(
+            "Examples:\n  censys-mock\n  \
+             censys-mock --listen 127.0.0.1:0 --hosts 500 --page_size 25\n  \
+             censys-mock --data hosts.ndjson --api_id id --secret secret\n  \
+             censys-mock --fault 429@2 --fault 503@4 --fault malformed@6",
+        )
+}
+
+fn read_hosts(path: &str) -> Result<Vec<Value>, String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    if contents.trim_start().starts_with('[') {
+        return serde_json::from_str(&contents).map_err(|err| err.to_string());
+    }
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(|err| err.to_string()))
+        .collect()
+}
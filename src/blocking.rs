@@ -0,0 +1,402 @@
+//! Blocking client, used by the `censys-search` binary.
+
+use crate::{
+    cache::{normalize_url, ResponseCache},
+    cassette::{Cassette, CassetteMode},
+    constants, get_cursor_from_response,
+    models::{Certificate, Host},
+    paths,
+    rate_limit::RateLimiter,
+    Error,
+};
+use reqwest::blocking::Client;
+use reqwest::header::{ACCEPT, AUTHORIZATION};
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::{collections::VecDeque, sync::Arc};
+
+#[derive(Clone)]
+pub struct CensysClient {
+    client: Client,
+    token: String,
+    base_url: String,
+    limiter: Arc<RateLimiter>,
+    cache: Option<Arc<ResponseCache>>,
+    cache_mode: CacheMode,
+    cassette: Option<Arc<Cassette>>,
+}
+
+/// How GET requests use the response cache.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CacheMode {
+    /// Answer from fresh entries and cache new responses.
+    Normal,
+    /// Ignore cached entries, but still replace them with new responses.
+    Refresh,
+    /// Answer only from cached entries, however old, and never send requests.
+    Offline,
+}
+
+impl CensysClient {
+    pub fn new(api_id: &str, secret: &str) -> Self {
+        CensysClient {
+            client: Client::new(),
+            token: base64::encode(format!("{}:{}", api_id, secret)),
+            base_url: constants::BASE_URL.to_owned(),
+            limiter: Arc::new(RateLimiter::new(None)),
+            cache: None,
+            cache_mode: CacheMode::Normal,
+            cassette: None,
+        }
+    }
+
+    /// Sends requests to another server, such as a local mock, instead of
+    /// `constants::BASE_URL`.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Starts no more than `per_second` requests per second across every clone
+    /// of this client.
+    pub fn with_rate_limit(mut self, per_second: Option<f64>) -> Self {
+        self.limiter = Arc::new(RateLimiter::new(per_second));
+        self
+    }
+
+    /// Answers GET requests from `cache` as `cache_mode` allows.
+    pub fn with_cache(mut self, cache: ResponseCache, cache_mode: CacheMode) -> Self {
+        self.cache = Some(Arc::new(cache));
+        self.cache_mode = cache_mode;
+        self
+    }
+
+    /// Records every request to `cassette`, or answers them from it without
+    /// sending anything, depending on how the cassette was opened.
+    pub fn with_cassette(mut self, cassette: Cassette) -> Self {
+        self.cassette = Some(Arc::new(cassette));
+        self
+    }
+
+    /// Sends a request to a path built with the `paths` module.
+    pub fn send_request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&Value>,
+    ) -> Result<Value, Error> {
+        let url = format!("{}{}", self.base_url, path);
+        let Some(cache) = &self.cache else {
+            return self.send_request_to_url(method, &url, body);
+        };
+        let key = normalize_url(&url);
+        if self.cache_mode == CacheMode::Offline {
+            if method != Method::GET {
+                return Err(Error::Offline(format!("cannot send {} {}", method, url)));
+            }
+            return cache
+                .get_stale(&key)
+                .ok_or_else(|| Error::Offline(format!("no cached response for {}", url)));
+        }
+        if method != Method::GET {
+            let json_response = self.send_request_to_url(method, &url, body)?;
+            let urls: Vec<String> = invalidated_paths(path)
+                .into_iter()
+                .map(|path| format!("{}{}", self.base_url, path))
+                .collect();
+            let _ = cache.invalidate(&urls);
+            return Ok(json_response);
+        }
+        if self.cache_mode == CacheMode::Normal {
+            if let Some(json_response) = cache.get(&key) {
+                return Ok(json_response);
+            }
+        }
+        let json_response = self.send_request_to_url(method, &url, body)?;
+        cache.insert(&key, &json_response);
+        Ok(json_response)
+    }
+
+    /// Fetches the account login and query quota. It is never cached.
+    pub fn account(&self) -> Result<Value, Error> {
+        self.send_request_to_url(Method::GET, &self.account_url(), None)
+    }
+
+    /// The account endpoint only exists in v1 of the API, next to the v2 base
+    /// URL. Under a base URL without a version, such as a local mock, it is
+    /// `/account`.
+    pub fn account_url(&self) -> String {
+        match self.base_url.strip_suffix("/v2") {
+            Some(api_url) => format!("{}/v1/account", api_url),
+            None => format!("{}/account", self.base_url),
+        }
+    }
+
+    /// Sends a request to an absolute URL, such as `account_url`. These
+    /// requests are never cached.
+    pub fn send_request_to_url(
+        &self,
+        method: Method,
+        url: &str,
+        body: Option<&Value>,
+    ) -> Result<Value, Error> {
+        if self.cache_mode == CacheMode::Offline {
+            return Err(Error::Offline(format!("cannot send {} {}", method, url)));
+        }
+        // Cassettes store URLs relative to the base URL so that a recording
+        // can be replayed against any server.
+        let cassette_url = url.strip_prefix(&self.base_url).unwrap_or(url);
+        let (status, text) = match &self.cassette {
+            Some(cassette) if cassette.mode() == CassetteMode::Replay => cassette
+                .find(method.as_str(), cassette_url, body)
+                .ok_or_else(|| {
+                    Error::Transport(format!(
+                        "{} has no recorded response for {} {}",
+                        cassette.path().display(),
+                        method,
+                        cassette_url
+                    ))
+                })?,
+            cassette => {
+                let method_name = method.to_string();
+                let (status, text) = self.send(method, url, body)?;
+                if let Some(cassette) = cassette {
+                    cassette
+                        .add(&method_name, cassette_url, body, status, &text)
+                        .map_err(|err| Error::Transport(err.to_string()))?;
+                }
+                (status, text)
+            }
+        };
+        let json_response = if text.trim().is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(&text)
+                .map_err(|err| Error::InvalidJson(status, err.to_string()))?
+        };
+        if (200..300).contains(&status) {
+            Ok(json_response)
+        } else {
+            Err(Error::Api(status, json_response))
+        }
+    }
+
+    fn send(
+        &self,
+        method: Method,
+        url: &str,
+        body: Option<&Value>,
+    ) -> Result<(u16, String), Error> {
+        self.limiter.wait();
+        let mut request = self
+            .client
+            .request(method, url)
+            .header(ACCEPT, "application/json")
+            .header(AUTHORIZATION, format!("Basic {}", self.token));
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        let response = request
+            .send()
+            .map_err(|err| Error::Transport(err.to_string()))?;
+        let status = response.status().as_u16();
+        let text = response
+            .text()
+            .map_err(|err| Error::Transport(err.to_string()))?;
+        Ok((status, text))
+    }
+
+    /// Searches hosts, see `Search` for how to consume the results.
+    pub fn search(&self, query: &str) -> Search<'_> {
+        self.paged(paths::make_path_from_query(query))
+    }
+
+    /// Searches certificates, returning only the given fields of each hit.
+    pub fn search_certificates(&self, query: &str, fields: &[&str]) -> Search<'_> {
+        self.paged(paths::make_path_from_cert_query(query, fields))
+    }
+
+    /// Pages through any endpoint that returns `result.links.next` cursors.
+    pub fn paged(&self, path: String) -> Search<'_> {
+        Search {
+            client: self,
+            path,
+            first_page: None,
+            max_pages: None,
+            max_results: None,
+            next_cursor: get_cursor_from_response,
+        }
+    }
+}
+
+/// Paths whose cached responses a change sent to `path` makes stale.
+///
+/// Comments and tags show up in the cached views of the host, certificate or
+/// tag they were changed on, which is the first two segments of `path`, such
+/// as `/hosts/192.0.2.1` for `/hosts/192.0.2.1/comments/3`. Any change to
+/// tags also changes the tag listings under `/tags`.
+fn invalidated_paths(path: &str) -> Vec<&str> {
+    let path = path.split('?').next().unwrap_or(path);
+    let resource = match path.match_indices('/').nth(2) {
+        Some((end, _)) => &path[..end],
+        None => path,
+    };
+    if resource == "/tags" || resource.starts_with("/tags/") {
+        vec!["/tags"]
+    } else if path.split('/').any(|segment| segment == "tags") {
+        vec![resource, "/tags"]
+    } else {
+        vec![resource]
+    }
+}
+
+/// A paged request. Nothing is sent until the pages or hits are iterated.
+pub struct Search<'a> {
+    client: &'a CensysClient,
+    path: String,
+    first_page: Option<Value>,
+    max_pages: Option<usize>,
+    max_results: Option<usize>,
+    next_cursor: fn(&Value) -> Option<String>,
+}
+
+impl<'a> Search<'a> {
+    /// Stops after this many pages have been returned.
+    pub fn max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = Some(max_pages);
+        self
+    }
+
+    /// Stops after this many hits. The last page is truncated to fit.
+    pub fn max_results(mut self, max_results: usize) -> Self {
+        self.max_results = Some(max_results);
+        self
+    }
+
+    /// Reads the cursor of the next page with `next_cursor` instead of from
+    /// `result.links.next`.
+    pub fn next_cursor(mut self, next_cursor: fn(&Value) -> Option<String>) -> Self {
+        self.next_cursor = next_cursor;
+        self
+    }
+
+    /// Uses a first page that has already been fetched instead of requesting it.
+    pub fn first_page(mut self, first_page: Value) -> Self {
+        self.first_page = Some(first_page);
+        self
+    }
+
+    pub fn pages(self) -> Pages<'a> {
+        Pages {
+            cursor: Some(None),
+            pages_returned: 0,
+            hits_returned: 0,
+            search: self,
+        }
+    }
+
+    pub fn hits(self) -> Hits<'a> {
+        Hits {
+            pages: self.pages(),
+            hits: VecDeque::new(),
+        }
+    }
+
+    /// Like `hits`, for host searches.
+    pub fn hosts(self) -> impl Iterator<Item = Result<Host, Error>> + 'a {
+        self.typed_hits()
+    }
+
+    /// Like `hits`, for certificate searches.
+    pub fn certificates(self) -> impl Iterator<Item = Result<Certificate, Error>> + 'a {
+        self.typed_hits()
+    }
+
+    fn typed_hits<T: DeserializeOwned>(self) -> impl Iterator<Item = Result<T, Error>> + 'a {
+        self.hits().map(|hit| {
+            let hit = hit?;
+            T::deserialize(&hit).map_err(|_| Error::UnexpectedResponse(hit))
+        })
+    }
+}
+
+/// Iterator over whole response pages. After an error it returns `None`.
+pub struct Pages<'a> {
+    search: Search<'a>,
+    // The cursor of the next page, or None once paging is done.
+    cursor: Option<Option<String>>,
+    pages_returned: usize,
+    hits_returned: usize,
+}
+
+impl Iterator for Pages<'_> {
+    type Item = Result<Value, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cursor = self.cursor.take()?;
+        let search = &mut self.search;
+        if search
+            .max_pages
+            .is_some_and(|max_pages| self.pages_returned >= max_pages)
+            || search
+                .max_results
+                .is_some_and(|max_results| self.hits_returned >= max_results)
+        {
+            return None;
+        }
+        let mut json_response = match search.first_page.take() {
+            Some(first_page) => first_page,
+            None => {
+                let path = match &cursor {
+                    Some(cursor) => paths::append_query_param(&search.path, "cursor", cursor),
+                    None => search.path.clone(),
+                };
+                match search.client.send_request(Method::GET, &path, None) {
+                    Ok(json_response) => json_response,
+                    Err(err) => return Some(Err(err)),
+                }
+            }
+        };
+        if let Value::Array(hits) = &mut json_response["result"]["hits"] {
+            if let Some(max_results) = search.max_results {
+                hits.truncate(max_results - self.hits_returned);
+            }
+            self.hits_returned += hits.len();
+        }
+        self.pages_returned += 1;
+        self.cursor = (search.next_cursor)(&json_response).map(Some);
+        Some(Ok(json_response))
+    }
+}
+
+/// Iterator over the hits of every page, fetching each page only once the
+/// hits of the previous one have been consumed. After an error it returns
+/// `None`.
+pub struct Hits<'a> {
+    pages: Pages<'a>,
+    hits: VecDeque<Value>,
+}
+
+impl Iterator for Hits<'_> {
+    type Item = Result<Value, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(hit) = self.hits.pop_front() {
+                return Some(Ok(hit));
+            }
+            let mut json_response = match self.pages.next()? {
+                Ok(json_response) => json_response,
+                Err(err) => return Some(Err(err)),
+            };
+            match json_response["result"]["hits"].take() {
+                Value::Array(hits) => self.hits.extend(hits),
+                _ => {
+                    self.pages.cursor = None;
+                    return Some(Err(Error::UnexpectedResponse(json_response)));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,306 @@
+//! On-disk cache of successful GET responses.
+//!
+//! Each response is stored in its own file, named after a hash of the
+//! normalized request URL. A file's modification time records when it was
+//! last read, so eviction can drop the least recently used entries first.
+
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+pub const DEFAULT_MAX_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Numbers the temporary files of concurrent inserts within this process.
+static TEMPORARY_FILES: AtomicUsize = AtomicUsize::new(0);
+
+pub struct ResponseCache {
+    dir: PathBuf,
+    ttl: Duration,
+    max_bytes: u64,
+    /// Running total of the entry sizes, measured on the first insert, so
+    /// that inserts only scan the directory once the total exceeds the cap.
+    /// Other processes sharing the directory are not counted until then.
+    bytes: Mutex<Option<u64>>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub expired: usize,
+    pub bytes: u64,
+}
+
+/// A cache file as seen by eviction, which only looks at file metadata.
+struct CacheFile {
+    path: PathBuf,
+    last_used: SystemTime,
+    bytes: u64,
+}
+
+/// A cache file together with the record stored in it.
+struct Entry {
+    file: CacheFile,
+    url: String,
+    stored_at: u64,
+}
+
+impl ResponseCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        ResponseCache {
+            dir: dir.into(),
+            ttl: DEFAULT_TTL,
+            max_bytes: DEFAULT_MAX_BYTES,
+            bytes: Mutex::new(None),
+        }
+    }
+
+    /// `CENSYS_CACHE_DIR`, or `censys-search` under the user's cache directory.
+    pub fn default_dir() -> Option<PathBuf> {
+        if let Some(dir) = env::var_os("CENSYS_CACHE_DIR") {
+            return Some(dir.into());
+        }
+        let cache_home = env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?;
+        Some(cache_home.join("censys-search"))
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the cached response for `url` unless it is older than the TTL.
+    pub fn get(&self, url: &str) -> Option<Value> {
+        self.read(url, false)
+    }
+
+    /// Returns the cached response for `url`, however old it is.
+    pub fn get_stale(&self, url: &str) -> Option<Value> {
+        self.read(url, true)
+    }
+
+    fn read(&self, url: &str, allow_expired: bool) -> Option<Value> {
+        let path = self.entry_path(url);
+        let mut record: Value = serde_json::from_slice(&fs::read(&path).ok()?).ok()?;
+        if record["url"] != url
+            || (!allow_expired && self.is_expired(record["stored_at"].as_u64()?))
+        {
+            return None;
+        }
+        // Reads count as uses for eviction. Failing to record one only makes
+        // the entry more likely to be evicted.
+        if let Ok(file) = fs::File::options().append(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Some(record["response"].take())
+    }
+
+    /// Stores a response, then evicts least recently used entries if the
+    /// cache has outgrown its size cap. Failures are ignored since the cache
+    /// is only an optimization.
+    pub fn insert(&self, url: &str, response: &Value) {
+        let record =
+            json!({ "url": url, "stored_at": unix_time(), "response": response }).to_string();
+        let path = self.entry_path(url);
+        let replaced = fs::metadata(&path).map_or(0, |metadata| metadata.len());
+        // Write to a temporary file of its own first so that concurrent
+        // readers never see a partial entry.
+        let temporary = path.with_extension(format!(
+            "tmp{}-{}",
+            std::process::id(),
+            TEMPORARY_FILES.fetch_add(1, Ordering::Relaxed)
+        ));
+        let written = fs::create_dir_all(&self.dir)
+            .and_then(|()| fs::write(&temporary, &record))
+            .and_then(|()| fs::rename(&temporary, &path));
+        if written.is_err() {
+            let _ = fs::remove_file(temporary);
+            return;
+        }
+        let over_cap = {
+            let mut bytes = self.bytes.lock().expect("Cache size lock poisoned");
+            let total = match *bytes {
+                Some(total) => total.saturating_sub(replaced) + record.len() as u64,
+                None => match self.files() {
+                    Ok(files) => files.iter().map(|file| file.bytes).sum(),
+                    Err(_) => return,
+                },
+            };
+            *bytes = Some(total);
+            total > self.max_bytes
+        };
+        if over_cap {
+            let _ = self.evict_to_fit();
+        }
+    }
+
+    /// Removes the entries for each of `urls` and for every path below them,
+    /// with any query. `https://host/hosts/192.0.2.1` covers
+    /// `https://host/hosts/192.0.2.1/comments` but not
+    /// `https://host/hosts/192.0.2.10`.
+    pub fn invalidate(&self, urls: &[String]) -> io::Result<usize> {
+        let mut removed = 0;
+        for entry in self.entries()? {
+            let covered = urls.iter().any(|url| {
+                entry.url.strip_prefix(url.as_str()).is_some_and(|rest| {
+                    rest.is_empty() || rest.starts_with('/') || rest.starts_with('?')
+                })
+            });
+            if covered {
+                fs::remove_file(entry.file.path)?;
+                removed += 1;
+            }
+        }
+        self.forget_size();
+        Ok(removed)
+    }
+
+    pub fn stats(&self) -> io::Result<CacheStats> {
+        let mut stats = CacheStats::default();
+        for entry in self.entries()? {
+            stats.entries += 1;
+            stats.bytes += entry.file.bytes;
+            if self.is_expired(entry.stored_at) {
+                stats.expired += 1;
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Removes every entry, returning how many there were.
+    pub fn clear(&self) -> io::Result<usize> {
+        let files = self.files()?;
+        for file in &files {
+            fs::remove_file(&file.path)?;
+        }
+        self.forget_size();
+        Ok(files.len())
+    }
+
+    /// Removes expired entries, then evicts entries over the size cap.
+    /// Returns how many entries were removed.
+    pub fn prune(&self) -> io::Result<usize> {
+        let mut removed = 0;
+        for entry in self.entries()? {
+            if self.is_expired(entry.stored_at) {
+                fs::remove_file(entry.file.path)?;
+                removed += 1;
+            }
+        }
+        self.forget_size();
+        Ok(removed + self.evict_to_fit()?)
+    }
+
+    /// Evicts by file size and modification time alone, so that inserting
+    /// does not have to read every other entry, and records the size left.
+    fn evict_to_fit(&self) -> io::Result<usize> {
+        let mut files = self.files()?;
+        let mut bytes: u64 = files.iter().map(|file| file.bytes).sum();
+        files.sort_by_key(|file| file.last_used);
+        let mut removed = 0;
+        for file in files {
+            if bytes <= self.max_bytes {
+                break;
+            }
+            fs::remove_file(&file.path)?;
+            bytes -= file.bytes;
+            removed += 1;
+        }
+        *self.bytes.lock().expect("Cache size lock poisoned") = Some(bytes);
+        Ok(removed)
+    }
+
+    /// Makes the next insert measure the cache again after entries were
+    /// removed without counting their sizes.
+    fn forget_size(&self) {
+        *self.bytes.lock().expect("Cache size lock poisoned") = None;
+    }
+
+    fn files(&self) -> io::Result<Vec<CacheFile>> {
+        let dir = match fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        let mut files = Vec::new();
+        for dir_entry in dir {
+            let path = dir_entry?.path();
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+            let metadata = fs::metadata(&path)?;
+            files.push(CacheFile {
+                last_used: metadata.modified()?,
+                bytes: metadata.len(),
+                path,
+            });
+        }
+        Ok(files)
+    }
+
+    /// Reads every cache file, skipping any that are not valid records.
+    fn entries(&self) -> io::Result<Vec<Entry>> {
+        let mut entries = Vec::new();
+        for file in self.files()? {
+            let Ok(record) = serde_json::from_slice::<Value>(&fs::read(&file.path)?) else {
+                continue;
+            };
+            entries.push(Entry {
+                url: record["url"].as_str().unwrap_or_default().to_owned(),
+                stored_at: record["stored_at"].as_u64().unwrap_or(0),
+                file,
+            });
+        }
+        Ok(entries)
+    }
+
+    fn entry_path(&self, url: &str) -> PathBuf {
+        let digest = Sha256::digest(url.as_bytes());
+        let name: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+        self.dir.join(name + ".json")
+    }
+
+    fn is_expired(&self, stored_at: u64) -> bool {
+        unix_time().saturating_sub(stored_at) >= self.ttl.as_secs()
+    }
+}
+
+/// Sorts the query parameters of `url` so that the same request always maps
+/// to the same cache entry, whatever order its parameters were added in.
+pub fn normalize_url(url: &str) -> String {
+    let Some((base, query)) = url.split_once('?') else {
+        return url.to_owned();
+    };
+    let mut params: Vec<&str> = query.split('&').filter(|param| !param.is_empty()).collect();
+    params.sort_unstable();
+    if params.is_empty() {
+        base.to_owned()
+    } else {
+        format!("{}?{}", base, params.join("&"))
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
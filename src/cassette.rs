@@ -0,0 +1,159 @@
+//! Recorded HTTP interactions ("cassettes") for deterministic runs.
+//!
+//! In record mode, every request the client sends is saved together with its
+//! response. In replay mode, requests are answered from the saved
+//! interactions and nothing is sent. Credentials are replaced with
+//! `<scrubbed>` before anything is written.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+const SCRUBBED: &str = "<scrubbed>";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CassetteMode {
+    Record,
+    Replay,
+}
+
+pub struct Cassette {
+    path: PathBuf,
+    mode: CassetteMode,
+    secrets: Vec<String>,
+    state: Mutex<State>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct State {
+    interactions: Vec<Interaction>,
+    #[serde(skip)]
+    used: Vec<bool>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Interaction {
+    method: String,
+    /// Relative to the client's base URL when the request was sent there.
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<Value>,
+    status: u16,
+    /// The response body when it was JSON, or None when it was empty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    response: Option<Value>,
+    /// The response body when it was not JSON.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    response_text: Option<String>,
+}
+
+impl Cassette {
+    /// Starts an empty cassette that is written to `path` as requests are made.
+    /// `secrets`, such as the API ID and secret, are scrubbed from everything
+    /// recorded.
+    pub fn record(path: impl Into<PathBuf>, secrets: &[&str]) -> Self {
+        Cassette {
+            path: path.into(),
+            mode: CassetteMode::Record,
+            secrets: secrets
+                .iter()
+                .filter(|secret| !secret.is_empty())
+                .map(|secret| secret.to_string())
+                .collect(),
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    pub fn replay(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let mut state: State = serde_json::from_slice(&fs::read(&path)?)?;
+        state.used = vec![false; state.interactions.len()];
+        Ok(Cassette {
+            path,
+            mode: CassetteMode::Replay,
+            secrets: Vec::new(),
+            state: Mutex::new(state),
+        })
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the status and body recorded for a request. Identical requests
+    /// are answered in the order they were recorded, and the last answer is
+    /// repeated once they run out.
+    pub fn find(&self, method: &str, url: &str, body: Option<&Value>) -> Option<(u16, String)> {
+        let mut state = self.state.lock().expect("Cassette lock poisoned");
+        let State { interactions, used } = &mut *state;
+        let matching: Vec<usize> = interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| {
+                interaction.method == method
+                    && interaction.url == url
+                    && interaction.body.as_ref() == body
+            })
+            .map(|(index, _)| index)
+            .collect();
+        let index = matching
+            .iter()
+            .copied()
+            .find(|index| !used[*index])
+            .or_else(|| matching.last().copied())?;
+        used[index] = true;
+        let interaction = &interactions[index];
+        let text = match (&interaction.response_text, &interaction.response) {
+            (Some(text), _) => text.to_owned(),
+            (None, Some(response)) => response.to_string(),
+            (None, None) => String::new(),
+        };
+        Some((interaction.status, text))
+    }
+
+    /// Adds an interaction and rewrites the cassette file.
+    pub fn add(
+        &self,
+        method: &str,
+        url: &str,
+        body: Option<&Value>,
+        status: u16,
+        text: &str,
+    ) -> io::Result<()> {
+        let (response, response_text) = if text.trim().is_empty() {
+            (None, None)
+        } else {
+            match serde_json::from_str(text) {
+                Ok(response) => (Some(response), None),
+                Err(_) => (None, Some(text.to_owned())),
+            }
+        };
+        let interaction = self.scrub(Interaction {
+            method: method.to_owned(),
+            url: url.to_owned(),
+            body: body.cloned(),
+            status,
+            response,
+            response_text,
+        })?;
+        let mut state = self.state.lock().expect("Cassette lock poisoned");
+        state.interactions.push(interaction);
+        fs::write(&self.path, serde_json::to_string_pretty(&*state)? + "\n")
+    }
+
+    fn scrub(&self, interaction: Interaction) -> io::Result<Interaction> {
+        let mut serialized = serde_json::to_string(&interaction)?;
+        for secret in &self.secrets {
+            serialized = serialized.replace(secret.as_str(), SCRUBBED);
+        }
+        Ok(serde_json::from_str(&serialized)?)
+    }
+}
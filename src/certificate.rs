@@ -0,0 +1,305 @@
+use serde_json::Value;
+use std::fmt::Write;
+
+/// Renders a certificate record in the layout of `openssl x509 -text`.
+pub fn render_text(record: &Value) -> String {
+    let parsed = &record["parsed"];
+    let mut text = String::from("Certificate:\n    Data:\n");
+    if let Some(version) = parsed["version"].as_u64() {
+        writeln!(text, "        Version: {}", version).unwrap();
+    }
+    write_field(&mut text, 8, "Serial Number", &parsed["serial_number"]);
+    write_field(
+        &mut text,
+        8,
+        "Signature Algorithm",
+        &parsed["signature"]["signature_algorithm"]["name"],
+    );
+    write_field(&mut text, 8, "Issuer", &parsed["issuer_dn"]);
+    writeln!(text, "        Validity").unwrap();
+    write_field(
+        &mut text,
+        12,
+        "Not Before",
+        &parsed["validity_period"]["not_before"],
+    );
+    write_field(
+        &mut text,
+        12,
+        "Not After ",
+        &parsed["validity_period"]["not_after"],
+    );
+    write_field(&mut text, 8, "Subject", &parsed["subject_dn"]);
+    write_public_key(&mut text, &parsed["subject_key_info"]);
+    write_extensions(&mut text, &parsed["extensions"]);
+
+    let signature = &parsed["signature"];
+    if !signature["self_signed"].is_null() || !signature["valid"].is_null() {
+        writeln!(text, "    Signature:").unwrap();
+        write_field(&mut text, 8, "Self Signed", &signature["self_signed"]);
+        write_field(&mut text, 8, "Valid", &signature["valid"]);
+    }
+    writeln!(text, "    Fingerprints:").unwrap();
+    write_field(&mut text, 8, "SHA-256", &record["fingerprint_sha256"]);
+    write_field(&mut text, 8, "SHA-1", &record["fingerprint_sha1"]);
+    write_field(&mut text, 8, "MD5", &record["fingerprint_md5"]);
+    write_ct_entries(&mut text, &record["ct"]["entries"]);
+    text.trim_end().to_owned()
+}
+
+fn write_public_key(text: &mut String, key_info: &Value) {
+    if !key_info.is_object() {
+        return;
+    }
+    writeln!(text, "        Subject Public Key Info:").unwrap();
+    write_field(
+        text,
+        12,
+        "Public Key Algorithm",
+        &key_info["key_algorithm"]["name"],
+    );
+    for algorithm in ["rsa", "ecdsa", "dsa", "ed25519"] {
+        let key = &key_info[algorithm];
+        if !key.is_object() {
+            continue;
+        }
+        if let Some(length) = key["length"].as_u64() {
+            writeln!(text, "                Public-Key: ({} bit)", length).unwrap();
+        }
+        write_field(text, 16, "Exponent", &key["exponent"]);
+        write_field(text, 16, "Curve", &key["curve"]);
+    }
+    write_field(text, 12, "SPKI SHA-256", &key_info["fingerprint_sha256"]);
+}
+
+fn write_extensions(text: &mut String, extensions: &Value) {
+    let Some(extensions) = extensions.as_object() else {
+        return;
+    };
+    if extensions.is_empty() {
+        return;
+    }
+    writeln!(text, "        X509v3 extensions:").unwrap();
+    for (name, value) in extensions {
+        let (title, body) = match name.as_str() {
+            "subject_alt_name" => ("Subject Alternative Name", render_alt_names(value)),
+            "basic_constraints" => {
+                let mut body = format!(
+                    "CA:{}",
+                    value["is_ca"]
+                        .as_bool()
+                        .unwrap_or(false)
+                        .to_string()
+                        .to_uppercase()
+                );
+                if let Some(max_path_len) = value["max_path_len"].as_u64() {
+                    write!(body, ", pathlen:{}", max_path_len).unwrap();
+                }
+                ("Basic Constraints", body)
+            }
+            "key_usage" => ("Key Usage", render_enabled_flags(value)),
+            "extended_key_usage" => ("Extended Key Usage", render_enabled_flags(value)),
+            "authority_key_id" => ("Authority Key Identifier", render_scalar(value)),
+            "subject_key_id" => ("Subject Key Identifier", render_scalar(value)),
+            "crl_distribution_points" => ("CRL Distribution Points", render_list(value)),
+            "authority_info_access" => {
+                let mut lines = Vec::new();
+                for url in value["ocsp_urls"].as_array().into_iter().flatten() {
+                    lines.push(format!("OCSP - URI:{}", render_scalar(url)));
+                }
+                for url in value["issuer_urls"].as_array().into_iter().flatten() {
+                    lines.push(format!("CA Issuers - URI:{}", render_scalar(url)));
+                }
+                ("Authority Information Access", lines.join("\n"))
+            }
+            "certificate_policies" => {
+                let policies = value
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|policy| format!("Policy: {}", render_scalar(&policy["id"])))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                ("Certificate Policies", policies)
+            }
+            "signed_certificate_timestamps" => {
+                let count = value.as_array().map_or(0, Vec::len);
+                ("CT Precertificate SCTs", format!("{} timestamps", count))
+            }
+            _ => (name.as_str(), value.to_string()),
+        };
+        writeln!(text, "            X509v3 {}:", title).unwrap();
+        for line in body.lines() {
+            writeln!(text, "                {}", line).unwrap();
+        }
+    }
+}
+
+fn write_ct_entries(text: &mut String, entries: &Value) {
+    let Some(entries) = entries.as_object() else {
+        return;
+    };
+    if entries.is_empty() {
+        return;
+    }
+    writeln!(text, "    CT Log Entries:").unwrap();
+    for (log, entry) in entries {
+        writeln!(
+            text,
+            "        {}: index {}, added {}",
+            log,
+            render_scalar(&entry["index"]),
+            render_scalar(&entry["added_to_ct_at"])
+        )
+        .unwrap();
+    }
+}
+
+fn write_field(text: &mut String, indent: usize, label: &str, value: &Value) {
+    if value.is_null() {
+        return;
+    }
+    writeln!(
+        text,
+        "{:indent$}{}: {}",
+        "",
+        label,
+        render_scalar(value),
+        indent = indent
+    )
+    .unwrap();
+}
+
+fn render_alt_names(value: &Value) -> String {
+    let prefixes = [
+        ("dns_names", "DNS"),
+        ("ip_addresses", "IP Address"),
+        ("email_addresses", "email"),
+        ("uniform_resource_identifiers", "URI"),
+    ];
+    prefixes
+        .iter()
+        .flat_map(|(field, prefix)| {
+            value[field]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(move |name| format!("{}:{}", prefix, render_scalar(name)))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn render_enabled_flags(value: &Value) -> String {
+    value
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(_, enabled)| enabled.as_bool() == Some(true))
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn render_list(value: &Value) -> String {
+    value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(render_scalar)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn render_scalar(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.to_owned(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_text;
+    use serde_json::{json, Value};
+
+    fn fixture() -> Value {
+        let response: Value =
+            serde_json::from_str(include_str!("../tests/fixtures/certificate.json")).unwrap();
+        response["result"].to_owned()
+    }
+
+    #[test]
+    fn full_certificates_are_rendered() {
+        let expected = [
+            "Certificate:",
+            "    Data:",
+            "        Version: 3",
+            "        Serial Number: 319482372639416725416093826471392804957",
+            "        Signature Algorithm: SHA256-RSA",
+            "        Issuer: C=US, O=Let's Encrypt, CN=R3",
+            "        Validity",
+            "            Not Before: 2022-12-01T04:12:57Z",
+            "            Not After : 2023-03-01T04:12:56Z",
+            "        Subject: CN=www.example.com",
+            "        Subject Public Key Info:",
+            "            Public Key Algorithm: ECDSA",
+            "                Public-Key: (256 bit)",
+            "                Curve: P-256",
+            "            SPKI SHA-256: 8a2f3d1c5b7e9f0a2c4e6b8d0f1a3c5e7b9d1f3a5c7e9b1d3f5a7c9e1b3d5f7a",
+            "        X509v3 extensions:",
+            "            X509v3 Authority Information Access:",
+            "                OCSP - URI:http://r3.o.lencr.org",
+            "                CA Issuers - URI:http://r3.i.lencr.org/",
+            "            X509v3 Authority Key Identifier:",
+            "                142eb317b75856cbae500940e61faf9d8b14c2c6",
+            "            X509v3 Basic Constraints:",
+            "                CA:FALSE",
+            "            X509v3 Certificate Policies:",
+            "                Policy: 2.23.140.1.2.1",
+            "                Policy: 1.3.6.1.4.1.44947.1.1.1",
+            "            X509v3 CRL Distribution Points:",
+            "                http://r3.c.lencr.org/",
+            "            X509v3 Extended Key Usage:",
+            "                client_auth, server_auth",
+            "            X509v3 Key Usage:",
+            "                digital_signature",
+            "            X509v3 CT Precertificate SCTs:",
+            "                2 timestamps",
+            "            X509v3 Subject Alternative Name:",
+            "                DNS:example.com, DNS:www.example.com, IP Address:192.0.2.1",
+            "            X509v3 Subject Key Identifier:",
+            "                d3a1f0b2c4e6a8b0d2f4a6c8e0b2d4f6a8c0e2b4",
+            "    Signature:",
+            "        Self Signed: false",
+            "        Valid: true",
+            "    Fingerprints:",
+            "        SHA-256: b5d1f7e6a3c2e4f8d9a0b1c2d3e4f5a6b7c8d9e0f1a2b3c4d5e6f7a8b9c0d1e2",
+            "        SHA-1: 3f1c9b0e5a7d2c4e6f8a0b1c3d5e7f9a1b3c5d7e",
+            "        MD5: 9e107d9d372bb6826bd81d3542a419d6",
+            "    CT Log Entries:",
+            "        google_argon_2023: index 418290133, added 2022-12-01T05:13:04Z",
+        ]
+        .join("\n");
+        assert_eq!(render_text(&fixture()), expected);
+    }
+
+    #[test]
+    fn absent_sections_are_left_out() {
+        let mut certificate = fixture();
+        certificate["parsed"]["signature"] = json!({});
+        certificate["parsed"]["extensions"] = json!({});
+        certificate["ct"] = json!({ "entries": {} });
+        let text = render_text(&certificate);
+        for heading in ["Signature", "X509v3 extensions", "CT Log Entries"] {
+            assert!(!text.contains(heading), "{}", text);
+        }
+        assert!(text.ends_with("MD5: 9e107d9d372bb6826bd81d3542a419d6"));
+
+        let text = render_text(&json!({ "fingerprint_sha256": "ab" }));
+        assert_eq!(
+            text,
+            "Certificate:\n    Data:\n        Validity\n    Fingerprints:\n        SHA-256: ab"
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use serde_json::Value;
+use std::fmt::Write;
+
+/// Renders the JSON patch returned by the host diff endpoint as a readable
+/// summary of the services that appeared, disappeared or changed.
+pub fn summarize(result: &Value) -> String {
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    // Changes per service, labelled from the patched document at the time of
+    // the first change to it.
+    let mut changed: Vec<(String, Vec<String>)> = Vec::new();
+    let mut other = Vec::new();
+
+    // Patch indices refer to the document as left by the preceding operations,
+    // so services are looked up in a copy that the patch is applied to.
+    let mut services = result["a"]["services"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let patch = result["patch"].as_array().map(Vec::as_slice).unwrap_or(&[]);
+    for operation in patch {
+        let op = operation["op"].as_str().unwrap_or("?");
+        let path = operation["path"].as_str().unwrap_or("");
+        let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+        match segments.as_slice() {
+            ["services", index] => {
+                let index = match *index {
+                    "-" => services.len(),
+                    index => index.parse().unwrap_or(usize::MAX),
+                };
+                match op {
+                    "add" => {
+                        added.push(describe_service(&operation["value"]));
+                        if index <= services.len() {
+                            services.insert(index, operation["value"].to_owned());
+                        }
+                    }
+                    "remove" if index < services.len() => {
+                        removed.push(describe_service(&services.remove(index)))
+                    }
+                    "remove" => removed.push(describe_service(&Value::Null)),
+                    _ => {
+                        record_change(&mut changed, &services, index, "replaced".to_owned());
+                        if index < services.len() && op == "replace" {
+                            services[index] = operation["value"].to_owned();
+                        }
+                    }
+                }
+            }
+            ["services", index, rest @ ..] => {
+                let index = index.parse().unwrap_or(usize::MAX);
+                let change = format!("{} {}", op_verb(op), categorize(rest));
+                record_change(&mut changed, &services, index, change);
+            }
+            _ => other.push(format!("{} {}", op_verb(op), path)),
+        }
+    }
+
+    let mut summary = String::new();
+    writeln!(summary, "{}", describe_snapshot("A", &result["a"])).unwrap();
+    writeln!(summary, "{}", describe_snapshot("B", &result["b"])).unwrap();
+    if patch.is_empty() {
+        writeln!(summary, "\nNo differences").unwrap();
+        return summary;
+    }
+    write_section(&mut summary, "Services added", &added);
+    write_section(&mut summary, "Services removed", &removed);
+    if !changed.is_empty() {
+        writeln!(summary, "\nServices changed:").unwrap();
+        for (label, changes) in changed.iter_mut() {
+            changes.sort();
+            changes.dedup();
+            writeln!(summary, "  {}: {}", label, changes.join(", ")).unwrap();
+        }
+    }
+    write_section(&mut summary, "Other changes", &other);
+    summary
+}
+
+fn record_change(
+    changed: &mut Vec<(String, Vec<String>)>,
+    services: &[Value],
+    index: usize,
+    change: String,
+) {
+    let label = match services.get(index) {
+        Some(service) => describe_service(service),
+        None => format!("service #{}", index),
+    };
+    match changed.iter_mut().find(|(existing, _)| *existing == label) {
+        Some((_, changes)) => changes.push(change),
+        None => changed.push((label, vec![change])),
+    }
+}
+
+fn write_section(summary: &mut String, heading: &str, lines: &[String]) {
+    if lines.is_empty() {
+        return;
+    }
+    writeln!(summary, "\n{}:", heading).unwrap();
+    for line in lines {
+        writeln!(summary, "  {}", line).unwrap();
+    }
+}
+
+fn describe_snapshot(label: &str, snapshot: &Value) -> String {
+    let ip = snapshot["ip"].as_str().unwrap_or("?");
+    match snapshot["last_updated_at"].as_str() {
+        Some(time) => format!("{}: {} at {}", label, ip, time),
+        None => format!("{}: {}", label, ip),
+    }
+}
+
+pub fn describe_service(service: &Value) -> String {
+    if !service.is_object() {
+        return "unknown service".to_owned();
+    }
+    let port = service["port"]
+        .as_u64()
+        .map(|port| port.to_string())
+        .unwrap_or_else(|| "?".to_owned());
+    let transport = service["transport_protocol"].as_str().unwrap_or("?");
+    let name = service["service_name"].as_str().unwrap_or("UNKNOWN");
+    format!("{}/{} {}", port, transport, name)
+}
+
+fn categorize(rest: &[&str]) -> &'static str {
+    match rest.first().copied() {
+        Some("port") | Some("transport_protocol") => "port",
+        Some("banner") | Some("banner_hex") | Some("banner_hashes") | Some("banner_grab") => {
+            "banner"
+        }
+        Some("certificate") | Some("tls") => "certificate",
+        Some("software") => "software",
+        Some("service_name") | Some("extended_service_name") => "service name",
+        _ => "details",
+    }
+}
+
+fn op_verb(op: &str) -> &'static str {
+    match op {
+        "add" => "added",
+        "remove" => "removed",
+        "replace" => "changed",
+        "move" => "moved",
+        "copy" => "copied",
+        _ => "modified",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::summarize;
+    use serde_json::{json, Value};
+
+    fn service(port: u64, name: &str) -> Value {
+        json!({ "port": port, "transport_protocol": "TCP", "service_name": name })
+    }
+
+    #[test]
+    fn removals_are_described_from_the_patched_document() {
+        let result = json!({
+            "a": {
+                "ip": "192.0.2.1",
+                "services": [service(22, "SSH"), service(80, "HTTP"), service(443, "HTTP")],
+            },
+            "b": { "ip": "192.0.2.1", "services": [service(80, "HTTP")] },
+            "patch": [
+                { "op": "remove", "path": "/services/0" },
+                { "op": "remove", "path": "/services/1" },
+            ],
+        });
+        let summary = summarize(&result);
+        assert!(
+            summary.contains("Services removed:\n  22/TCP SSH\n  443/TCP HTTP\n"),
+            "{}",
+            summary
+        );
+    }
+
+    #[test]
+    fn changes_are_labelled_from_the_patched_document() {
+        let result = json!({
+            "a": { "ip": "192.0.2.1", "services": [service(22, "SSH"), service(80, "HTTP")] },
+            "b": {
+                "ip": "192.0.2.1",
+                "services": [service(21, "FTP"), service(22, "SSH"), service(8080, "HTTP")],
+            },
+            "patch": [
+                { "op": "replace", "path": "/services/1/banner", "value": "nginx" },
+                { "op": "add", "path": "/services/0", "value": service(21, "FTP") },
+                { "op": "replace", "path": "/services/1/software", "value": [] },
+                { "op": "replace", "path": "/services/2", "value": service(8080, "HTTP") },
+                { "op": "replace", "path": "/services/2/banner", "value": "Jetty" },
+            ],
+        });
+        let summary = summarize(&result);
+        assert!(
+            summary.contains(
+                "Services changed:\n  \
+                 80/TCP HTTP: changed banner, replaced\n  \
+                 22/TCP SSH: changed software\n  \
+                 8080/TCP HTTP: changed banner\n"
+            ),
+            "{}",
+            summary
+        );
+    }
+}
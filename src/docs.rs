@@ -0,0 +1,131 @@
+use clap::{Arg, Command};
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+/// Renders the top-level page. The `help` subcommand is left out, since
+/// `write_man_pages` does not generate a page for it.
+pub fn render_man_page(cmd: Command, out: &mut dyn Write) -> io::Result<()> {
+    clap_mangen::Man::new(cmd.disable_help_subcommand(true)).render(out)
+}
+
+/// Writes one page per command.
+pub fn write_man_pages(cmd: Command, out_dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(out_dir)?;
+    clap_mangen::generate_to(cmd.disable_help_subcommand(true), out_dir)
+}
+
+/// Writes the whole reference to a single file named after the command.
+pub fn write_markdown(cmd: Command, out_dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(out_dir)?;
+    let path = out_dir.join(format!("{}.md", cmd.get_name()));
+    fs::write(path, render_markdown(cmd))
+}
+
+pub fn render_markdown(cmd: Command) -> String {
+    let mut cmd = cmd.disable_help_subcommand(true);
+    cmd.build();
+    let mut markdown = String::new();
+    writeln!(markdown, "# {}", cmd.get_name()).unwrap();
+    if let Some(version) = cmd.get_version() {
+        writeln!(markdown, "\nVersion {}", version).unwrap();
+    }
+    render_markdown_section(&mut cmd, &mut markdown, 1);
+    markdown
+}
+
+fn render_markdown_section(cmd: &mut Command, markdown: &mut String, depth: usize) {
+    if depth > 1 {
+        let name = cmd
+            .get_bin_name()
+            .unwrap_or_else(|| cmd.get_name())
+            .to_owned();
+        writeln!(markdown, "\n{} `{}`", "#".repeat(depth), name).unwrap();
+    }
+    if let Some(about) = cmd.get_long_about().or_else(|| cmd.get_about()) {
+        writeln!(markdown, "\n{}", about).unwrap();
+    }
+    writeln!(
+        markdown,
+        "\n```\n{}\n```",
+        cmd.render_usage().to_string().trim_end()
+    )
+    .unwrap();
+
+    let positionals: Vec<&Arg> = cmd.get_positionals().collect();
+    if !positionals.is_empty() {
+        writeln!(markdown, "\n**Arguments:**\n").unwrap();
+        for arg in positionals {
+            writeln!(markdown, "- `<{}>`: {}", arg.get_id(), arg_help(arg)).unwrap();
+        }
+    }
+
+    let options: Vec<&Arg> = cmd
+        .get_arguments()
+        .filter(|arg| !arg.is_positional())
+        .collect();
+    if !options.is_empty() {
+        writeln!(markdown, "\n**Options:**\n").unwrap();
+        for arg in options {
+            writeln!(markdown, "- `{}`: {}", arg_invocation(arg), arg_help(arg)).unwrap();
+        }
+    }
+
+    let subcommands: Vec<&Command> = cmd
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set())
+        .collect();
+    if !subcommands.is_empty() {
+        writeln!(markdown, "\n**Subcommands:**\n").unwrap();
+        for sub in subcommands {
+            let about = sub
+                .get_about()
+                .map(|about| about.to_string())
+                .unwrap_or_default();
+            writeln!(markdown, "- `{}`: {}", sub.get_name(), about).unwrap();
+        }
+    }
+
+    if let Some(after_help) = cmd.get_after_long_help().or_else(|| cmd.get_after_help()) {
+        writeln!(
+            markdown,
+            "\n```\n{}\n```",
+            after_help.to_string().trim_end()
+        )
+        .unwrap();
+    }
+
+    for sub in cmd.get_subcommands_mut().filter(|sub| !sub.is_hide_set()) {
+        render_markdown_section(sub, markdown, depth + 1);
+    }
+}
+
+fn arg_invocation(arg: &Arg) -> String {
+    let mut names = Vec::new();
+    if let Some(short) = arg.get_short() {
+        names.push(format!("-{}", short));
+    }
+    if let Some(long) = arg.get_long() {
+        names.push(format!("--{}", long));
+    }
+    let mut invocation = names.join(", ");
+    if arg.get_action().takes_values() {
+        let value_name = arg
+            .get_value_names()
+            .and_then(|names| names.first())
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| arg.get_id().to_string().to_uppercase());
+        write!(invocation, " <{}>", value_name).unwrap();
+    }
+    invocation
+}
+
+fn arg_help(arg: &Arg) -> String {
+    arg.get_long_help()
+        .or_else(|| arg.get_help())
+        .map(|help| help.to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,38 @@
+use serde_json::Value;
+use std::fmt::{self, Display};
+
+/// Failure of a single API request.
+#[derive(Debug)]
+pub enum Error {
+    /// The request could not be sent or the response could not be read.
+    Transport(String),
+    /// The response body with the given HTTP status was not valid JSON.
+    InvalidJson(u16, String),
+    /// The API answered with a non-success HTTP status and this body.
+    Api(u16, Value),
+    /// The response was missing the fields the endpoint is documented to return.
+    UnexpectedResponse(Value),
+    /// The request could not be answered from the cache in offline mode.
+    Offline(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Transport(message) => write!(f, "{}", message),
+            Error::InvalidJson(status, message) => {
+                write!(f, "invalid JSON in response (HTTP {}): {}", status, message)
+            }
+            Error::Api(status, json_response) => match json_response["error"].as_str() {
+                Some(error) => write!(f, "HTTP {}: {}", status, error),
+                None => write!(f, "HTTP {}: {}", status, json_response),
+            },
+            Error::UnexpectedResponse(json_response) => {
+                write!(f, "unexpected response: {}", json_response)
+            }
+            Error::Offline(message) => write!(f, "offline: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
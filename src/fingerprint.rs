@@ -0,0 +1,208 @@
+use sha2::{Digest, Sha256};
+use std::{fs, path::Path};
+
+const PEM_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+pub struct CertificateHashes {
+    pub sha256: String,
+    pub spki_sha256: String,
+}
+
+/// Accepts a SHA-256 fingerprint with optional colon separators in any case and
+/// normalizes it to the 64 lowercase hex characters the API expects.
+pub fn parse_fingerprint(value: &str) -> Result<String, String> {
+    let fingerprint: String = value
+        .chars()
+        .filter(|c| *c != ':' && !c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase();
+    if fingerprint.len() == 64 && fingerprint.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(fingerprint)
+    } else {
+        Err(format!(
+            "'{}' is not a SHA-256 fingerprint (expected 64 hex characters)",
+            value
+        ))
+    }
+}
+
+/// Hashes the first certificate in a PEM or DER encoded file.
+pub fn hash_certificate_file(path: &Path) -> Result<CertificateHashes, String> {
+    let contents =
+        fs::read(path).map_err(|err| format!("Unable to read {}: {}", path.display(), err))?;
+    let der = decode_pem(&contents).unwrap_or(Ok(contents))?;
+    hash_certificate(&der).map_err(|err| format!("{} {}", path.display(), err))
+}
+
+fn hash_certificate(der: &[u8]) -> Result<CertificateHashes, String> {
+    let invalid = || "is not a valid X.509 certificate".to_owned();
+    let (certificate, trailing) = read_element(der).ok_or_else(invalid)?;
+    if !trailing.is_empty() {
+        return Err(format!(
+            "has {} bytes of trailing data after the certificate",
+            trailing.len()
+        ));
+    }
+    let spki = find_subject_public_key_info(certificate).ok_or_else(invalid)?;
+    Ok(CertificateHashes {
+        sha256: sha256_hex(certificate),
+        spki_sha256: sha256_hex(spki),
+    })
+}
+
+fn decode_pem(contents: &[u8]) -> Option<Result<Vec<u8>, String>> {
+    let text = std::str::from_utf8(contents).ok()?;
+    let start = text.find(PEM_BEGIN)? + PEM_BEGIN.len();
+    let end = start + text[start..].find(PEM_END)?;
+    let body: String = text[start..end]
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    Some(base64::decode(body).map_err(|err| format!("Invalid PEM encoding: {}", err)))
+}
+
+/// Walks the DER encoding of a certificate down to the SubjectPublicKeyInfo
+/// field of the TBSCertificate.
+fn find_subject_public_key_info(der: &[u8]) -> Option<&[u8]> {
+    let (certificate, _) = read_element(der)?;
+    let (tbs_certificate, _) = read_element(contents(certificate)?)?;
+    let mut rest = contents(tbs_certificate)?;
+    // The version is an explicitly tagged [0] field that may be omitted.
+    if rest.first() == Some(&0xa0) {
+        rest = read_element(rest)?.1;
+    }
+    // Skip serialNumber, signature, issuer, validity and subject.
+    for _ in 0..5 {
+        rest = read_element(rest)?.1;
+    }
+    let (spki, _) = read_element(rest)?;
+    Some(spki)
+}
+
+/// Splits the first DER element (tag, length and contents) off the input.
+fn read_element(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (header_length, content_length) = read_header(data)?;
+    let total = header_length.checked_add(content_length)?;
+    (total <= data.len()).then(|| data.split_at(total))
+}
+
+fn contents(element: &[u8]) -> Option<&[u8]> {
+    let (header_length, _) = read_header(element)?;
+    element.get(header_length..)
+}
+
+fn read_header(data: &[u8]) -> Option<(usize, usize)> {
+    let first_length_byte = *data.get(1)?;
+    if first_length_byte & 0x80 == 0 {
+        return Some((2, first_length_byte as usize));
+    }
+    let length_bytes = (first_length_byte & 0x7f) as usize;
+    if length_bytes == 0 || length_bytes > std::mem::size_of::<usize>() {
+        return None;
+    }
+    let length = data
+        .get(2..2 + length_bytes)?
+        .iter()
+        .fold(0usize, |length, byte| (length << 8) | *byte as usize);
+    Some((2 + length_bytes, length))
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBiTCCAS+gAwIBAgIUDG7vHxkO6v8p9sWeYul8uiuknxcwCgYIKoZIzj0EAwIw
+GjEYMBYGA1UEAwwPd3d3LmV4YW1wbGUuY29tMB4XDTI2MTAxODE5MzQxMFoXDTM2
+MTAxNTE5MzQxMFowGjEYMBYGA1UEAwwPd3d3LmV4YW1wbGUuY29tMFkwEwYHKoZI
+zj0CAQYIKoZIzj0DAQcDQgAEpleI9SKb2STK+PdQqbzoAIdpdC1L9/janI+f76xU
+mKS9uNfExhsUjYgqh9GceSKmZfdQoJI0taO8zfuj5PHt8qNTMFEwHQYDVR0OBBYE
+FODgE0Suqd60uXGSUnkVn/TfOEg/MB8GA1UdIwQYMBaAFODgE0Suqd60uXGSUnkV
+n/TfOEg/MA8GA1UdEwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSAAwRQIgO+QOYRhw
+WUSJVaWyvipUgEDR9FFMzFGwqpJpmlrNbDsCIQCJOYvxPO/9eZQNnx1zEQjy3nqF
+zx7mnEDVZdcvNw1sDg==
+-----END CERTIFICATE-----
+";
+
+    fn der() -> Vec<u8> {
+        decode_pem(CERTIFICATE.as_bytes()).unwrap().unwrap()
+    }
+
+    #[test]
+    fn fingerprints_are_normalized() {
+        let expected = "5e866734fd39b2f55df12f7f36e28756a2e889e5fdad756c6cd0b5e25349c8b9";
+        assert_eq!(parse_fingerprint(expected).unwrap(), expected);
+        let separated = expected
+            .to_uppercase()
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| std::str::from_utf8(pair).unwrap())
+            .collect::<Vec<_>>()
+            .join(":");
+        assert_eq!(parse_fingerprint(&separated).unwrap(), expected);
+        assert_eq!(
+            parse_fingerprint(&format!(" {} ", expected)).unwrap(),
+            expected
+        );
+        for value in [
+            "",
+            &expected[1..],
+            &format!("{}0", expected),
+            &expected.replace('e', "g"),
+        ] {
+            assert!(parse_fingerprint(value)
+                .unwrap_err()
+                .contains("is not a SHA-256 fingerprint"));
+        }
+    }
+
+    #[test]
+    fn certificate_and_public_key_are_hashed() {
+        let hashes = hash_certificate(&der()).unwrap();
+        assert_eq!(
+            hashes.sha256,
+            "5e866734fd39b2f55df12f7f36e28756a2e889e5fdad756c6cd0b5e25349c8b9"
+        );
+        assert_eq!(
+            hashes.spki_sha256,
+            "920c77f781111be10dc3fecdfa6056ef7d69bab027ca4698be5672d22c5325f8"
+        );
+    }
+
+    #[test]
+    fn trailing_and_truncated_data_is_rejected() {
+        let mut der = der();
+        der.extend_from_slice(b"\0\0\0");
+        assert_eq!(
+            hash_certificate(&der).err().unwrap(),
+            "has 3 bytes of trailing data after the certificate"
+        );
+        der.truncate(der.len() - 10);
+        assert_eq!(
+            hash_certificate(&der).err().unwrap(),
+            "is not a valid X.509 certificate"
+        );
+        assert!(find_subject_public_key_info(&[0x30, 0x03, 0x30, 0x01, 0x00]).is_none());
+    }
+
+    #[test]
+    fn der_headers_with_long_lengths() {
+        assert_eq!(read_header(&[0x30, 0x05]), Some((2, 5)));
+        assert_eq!(read_header(&[0x30, 0x82, 0x01, 0x89]), Some((4, 0x189)));
+        assert_eq!(read_header(&[0x30, 0x80]), None);
+        assert_eq!(read_header(&[0x30, 0x82, 0x01]), None);
+        assert_eq!(read_element(&[0x04, 0x02, 0xaa]), None);
+        assert_eq!(
+            read_element(&[0x04, 0x01, 0xaa, 0xbb]),
+            Some((&[0x04, 0x01, 0xaa][..], &[0xbb][..]))
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use crate::diff::describe_service;
+use serde_json::Value;
+
+/// The events endpoint keeps handing out a cursor after the last event so that
+/// callers can poll for new ones, so paging stops at the first empty page.
+pub fn get_cursor_from_events_response(json_response: &Value) -> Option<String> {
+    let result = &json_response["result"];
+    let has_events = result["events"]
+        .as_array()
+        .is_some_and(|events| !events.is_empty());
+    match &result["links"]["next"] {
+        Value::String(value) if has_events && !value.is_empty() => Some(value.to_owned()),
+        _ => None,
+    }
+}
+
+pub fn format_event(event: &Value) -> String {
+    let timestamp = event["timestamp"].as_str().unwrap_or("?");
+    let kind = event["_event"].as_str().unwrap_or("unknown");
+    let details = &event[kind];
+    let description = match kind {
+        "service_observed" => {
+            let changed = details["changed_fields"]
+                .as_array()
+                .map(|fields| {
+                    fields
+                        .iter()
+                        .filter_map(|field| field["field_name"].as_str().or(field.as_str()))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .unwrap_or_default();
+            if changed.is_empty() {
+                describe_service(&details["id"])
+            } else {
+                format!(
+                    "{} (changed: {})",
+                    describe_service(&details["id"]),
+                    changed
+                )
+            }
+        }
+        "end_service_observation" => describe_service(&details["id"]),
+        "location_updated" => {
+            let location = &details["location"];
+            [
+                &location["city"],
+                &location["province"],
+                &location["country"],
+            ]
+            .iter()
+            .filter_map(|part| part.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+        }
+        "route_updated" => {
+            let system = &details["route"]["autonomous_system"];
+            format!(
+                "AS{} {} ({})",
+                system["asn"]
+                    .as_u64()
+                    .map(|asn| asn.to_string())
+                    .unwrap_or_else(|| "?".to_owned()),
+                system["name"].as_str().unwrap_or("?"),
+                system["bgp_prefix"].as_str().unwrap_or("?")
+            )
+        }
+        _ => details.to_string(),
+    };
+    format!("{:<20}  {:<24}  {}", timestamp, kind, description)
+}
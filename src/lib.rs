@@ -0,0 +1,25 @@
+//! Client library for the Censys Search API, used by the `censys-search` binary.
+
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod blocking;
+pub mod cache;
+pub mod cassette;
+pub mod constants;
+mod error;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod models;
+pub mod paths;
+pub mod query;
+pub mod rate_limit;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+pub use error::Error;
+
+pub fn get_cursor_from_response(json_response: &Value) -> Option<String> {
+    let links = models::Links::deserialize(&json_response["result"]["links"]).ok()?;
+    links.next_cursor().map(str::to_owned)
+}
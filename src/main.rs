@@ -1,15 +1,590 @@
-use clap::{arg, ArgAction, Command};
-use reqwest::blocking::Client;
-use reqwest::header::{ACCEPT, AUTHORIZATION};
-use serde_json::Value;
-use std::{env, fs, io::Write, path::Path, process};
+use censys_search::{
+    blocking::{CacheMode, CensysClient, Pages, Search},
+    cache::ResponseCache,
+    cassette::Cassette,
+    constants,
+    paths::*,
+    query::Query,
+    Error,
+};
+use clap::{arg, Arg, ArgAction, ArgGroup, ArgMatches, Command};
+use reqwest::Method;
+use serde_json::{json, Value};
+use std::{
+    collections::HashSet,
+    env,
+    fmt::Display,
+    fs,
+    io::{self, Read, Write},
+    path::Path,
+    process,
+    time::Duration,
+};
 
-mod constants;
+mod aggregate;
+mod certificate;
+mod diff;
+mod docs;
+mod fingerprint;
+mod history;
+mod pool;
+mod sqlite;
+mod timestamp;
+
+const BULK_BATCH_SIZE: usize = 50;
 
 fn main() {
-    let arg_matches = Command::new("censys-search")
-        .version("1.0")
-        .about("Censys Search API utility")
+    let arg_matches = build_cli().get_matches();
+
+    match arg_matches.subcommand() {
+        Some(("fields", _)) => {
+            println!("{}", constants::CENSYS_SEARCH_FIELDS);
+            return;
+        }
+        Some(("man", man_command)) => {
+            let markdown = *man_command
+                .get_one::<bool>("markdown")
+                .expect("Argument always has a value");
+            let out_dir = man_command.get_one::<String>("out_dir").map(Path::new);
+            let result = match (markdown, out_dir) {
+                (true, Some(dir)) => docs::write_markdown(build_cli(), dir),
+                (true, None) => {
+                    print!("{}", docs::render_markdown(build_cli()));
+                    Ok(())
+                }
+                (false, Some(dir)) => docs::write_man_pages(build_cli(), dir),
+                (false, None) => docs::render_man_page(build_cli(), &mut io::stdout()),
+            };
+            if let Err(err) = result {
+                eprintln!("{}", err);
+                process::exit(1);
+            }
+            return;
+        }
+        Some(("local", local_command)) => {
+            let query = local_command
+                .get_one::<Query>("query")
+                .expect("Argument is required");
+            let data = local_command
+                .get_one::<String>("data")
+                .expect("Argument is required");
+            let count = *local_command
+                .get_one::<bool>("count")
+                .expect("Argument always has a value");
+            let out_file = arg_matches.get_one::<String>("output").map(Path::new);
+            let mut matched = 0;
+            for (index, line) in read_to_string(data).lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let record = serde_json::from_str(line).unwrap_or_else(|err| {
+                    eprintln!("Unable to parse line {} of {}: {}", index + 1, data, err);
+                    process::exit(1);
+                });
+                for host in saved_hosts(record) {
+                    if query.matches(&host) {
+                        matched += 1;
+                        if !count {
+                            write_to_file_or_std_out(&host, out_file);
+                        }
+                    }
+                }
+            }
+            if count {
+                write_to_file_or_std_out(&matched, out_file);
+            }
+            return;
+        }
+        Some(("cache", cache_command)) => {
+            let Some(cache) = response_cache(&arg_matches) else {
+                eprintln!("No cache directory, set CENSYS_CACHE_DIR");
+                process::exit(1);
+            };
+            let result = match cache_command.subcommand_name() {
+                Some("stats") => cache.stats().map(|stats| {
+                    println!(
+                        "Directory: {}\nEntries:   {} ({} expired)\nSize:      {} / {}",
+                        cache.dir().display(),
+                        stats.entries,
+                        stats.expired,
+                        format_megabytes(stats.bytes),
+                        format_megabytes(cache_size_bytes(&arg_matches))
+                    )
+                }),
+                Some("clear") => cache
+                    .clear()
+                    .map(|removed| println!("Removed {} cached responses", removed)),
+                Some("prune") => cache
+                    .prune()
+                    .map(|removed| println!("Removed {} cached responses", removed)),
+                _ => unreachable!("Subcommand is required"),
+            };
+            if let Err(err) = result {
+                eprintln!("{}", err);
+                process::exit(1);
+            }
+            return;
+        }
+        _ => {}
+    }
+
+    let offline = *arg_matches
+        .get_one::<bool>("offline")
+        .expect("Argument always has a value");
+    if offline {
+        exit_on_relative_time(&arg_matches);
+    }
+    let record = arg_matches.get_one::<String>("record");
+    let replay = arg_matches.get_one::<String>("replay");
+    // Offline and replayed runs never send requests, so they need no
+    // credentials.
+    let (api_id, secret) = if offline || replay.is_some() {
+        (String::new(), String::new())
+    } else {
+        let api_id = match arg_matches.get_one::<String>("api_id") {
+            Some(value) => value.to_owned(),
+            None => get_env_or_exit("CENSYS_API_ID"),
+        };
+        let secret = match arg_matches.get_one::<String>("secret") {
+            Some(value) => value.to_owned(),
+            None => get_env_or_exit("CENSYS_SECRET"),
+        };
+        (api_id, secret)
+    };
+    let no_paging = *arg_matches
+        .get_one::<bool>("no_paging")
+        .expect("Argument always has a value");
+    let out_file = arg_matches.get_one::<String>("output").map(Path::new);
+    let concurrency = *arg_matches
+        .get_one::<u16>("concurrency")
+        .expect("Argument has a default value") as usize;
+    let rate_limit = arg_matches
+        .get_one::<u32>("rate_limit")
+        .map(|per_second| *per_second as f64);
+    let no_cache = *arg_matches
+        .get_one::<bool>("no_cache")
+        .expect("Argument always has a value");
+    let refresh = *arg_matches
+        .get_one::<bool>("refresh")
+        .expect("Argument always has a value");
+    let mut client = CensysClient::new(&api_id, &secret)
+        .with_base_url(&base_url())
+        .with_rate_limit(rate_limit);
+    let cache_mode = if offline {
+        CacheMode::Offline
+    } else if refresh {
+        CacheMode::Refresh
+    } else {
+        CacheMode::Normal
+    };
+    if let Some(path) = record {
+        // The encoded Basic auth token is scrubbed too, in case a server echoes
+        // request headers back.
+        let token = base64::encode(format!("{}:{}", api_id, secret));
+        client = client.with_cassette(Cassette::record(path, &[&api_id, &secret, &token]));
+    } else if let Some(path) = replay {
+        match Cassette::replay(path) {
+            Ok(cassette) => client = client.with_cassette(cassette),
+            Err(err) => {
+                eprintln!("Failed to read cassette {}: {}", path, err);
+                process::exit(1);
+            }
+        }
+    } else {
+        // Cassettes should see every request, so they are never combined with
+        // the cache.
+        match response_cache(&arg_matches) {
+            Some(cache) if !no_cache => client = client.with_cache(cache, cache_mode),
+            None if offline => {
+                eprintln!("No cache directory to work offline from, set CENSYS_CACHE_DIR");
+                process::exit(1);
+            }
+            _ => {}
+        }
+    }
+
+    match arg_matches.subcommand() {
+        Some(("query", query_command)) => {
+            let query = query_command
+                .get_one::<String>("query")
+                .expect("Argument is required");
+            let path = make_path_from_query(query);
+            let check_quota = *query_command
+                .get_one::<bool>("check_quota")
+                .expect("Argument always has a value");
+            let mut results = results_or_exit(query_command, out_file);
+            let mut search = paged(&client, &path, no_paging);
+            if check_quota {
+                search = search.first_page(check_quota_or_exit(&client, &path, no_paging));
+            }
+            write_host_pages(search.pages(), "query", query, &mut results);
+        }
+        Some(("account", account_command)) => {
+            let json_response = exit_on_api_error(client.account());
+            match account_command
+                .get_one::<String>("format")
+                .map(String::as_str)
+            {
+                Some("json") => write_to_file_or_std_out(&json_response, out_file),
+                _ => {
+                    if !json_response["quota"].is_object() {
+                        eprintln!("{}", json_response);
+                        process::exit(1);
+                    }
+                    write_to_file_or_std_out(&format_account(&json_response), out_file);
+                }
+            }
+        }
+        Some(("aggregate", aggregate_command)) => {
+            let query = aggregate_command
+                .get_one::<String>("query")
+                .expect("Argument is required");
+            let field = aggregate_command
+                .get_one::<String>("field")
+                .expect("Argument is required");
+            let buckets = *aggregate_command
+                .get_one::<u16>("buckets")
+                .expect("Argument has a default value");
+            let path = make_aggregate_path_from_query(query, field, buckets);
+            let json_response = send_request(&client, &path);
+            let result = &json_response["result"];
+            if !result["buckets"].is_array() {
+                eprintln!("{}", json_response);
+                process::exit(1);
+            }
+            match aggregate_command
+                .get_one::<String>("format")
+                .map(String::as_str)
+            {
+                Some("json") => write_to_file_or_std_out(&json_response, out_file),
+                Some("csv") => write_to_file_or_std_out(&aggregate::render_csv(result), out_file),
+                _ => write_to_file_or_std_out(&aggregate::render_histogram(result), out_file),
+            }
+        }
+        Some(("ip", ip_command)) => match ip_command.subcommand() {
+            Some(("comments", comments_command)) => {
+                handle_comments_command(
+                    &client,
+                    comments_command,
+                    |command| {
+                        let address = command
+                            .get_one::<String>("address")
+                            .expect("Argument is required");
+                        make_comments_path_from_ip(address)
+                    },
+                    no_paging,
+                    out_file,
+                );
+            }
+            _ => {
+                let at_time = ip_command.get_one::<String>("at_time");
+                let mut results = results_or_exit(ip_command, out_file);
+                match ip_command.get_one::<String>("input") {
+                    Some(input) => {
+                        let items = read_lines(input)
+                            .into_iter()
+                            .map(|address| {
+                                let path = make_path_from_ip(&address, at_time.map(String::as_str));
+                                (address, path)
+                            })
+                            .collect();
+                        run_batch(&client, "ip", items, no_paging, concurrency, &mut results);
+                    }
+                    None => {
+                        let address = ip_command
+                            .get_one::<String>("address")
+                            .expect("Argument is required");
+                        let path = make_path_from_ip(address, at_time.map(String::as_str));
+                        let pages = paged(&client, &path, no_paging).pages();
+                        write_host_pages(pages, "ip", address, &mut results);
+                    }
+                }
+            }
+        },
+        Some(("diff", diff_command)) => {
+            let address = diff_command
+                .get_one::<String>("address")
+                .expect("Argument is required");
+            let path = make_diff_path_from_ip(
+                address,
+                diff_command.get_one::<String>("ip_b").map(String::as_str),
+                diff_command
+                    .get_one::<String>("at_time_a")
+                    .map(String::as_str),
+                diff_command
+                    .get_one::<String>("at_time_b")
+                    .map(String::as_str),
+            );
+            let json_response = exit_on_api_error(client.send_request(Method::GET, &path, None));
+            match diff_command.get_one::<String>("format").map(String::as_str) {
+                Some("json") => write_to_file_or_std_out(&json_response, out_file),
+                _ => {
+                    if !json_response["result"].is_object() {
+                        eprintln!("{}", json_response);
+                        process::exit(1);
+                    }
+                    let summary = diff::summarize(&json_response["result"]);
+                    write_to_file_or_std_out(summary.trim_end(), out_file);
+                }
+            }
+        }
+        Some(("history", history_command)) => {
+            let address = history_command
+                .get_one::<String>("address")
+                .expect("Argument is required");
+            let reversed = *history_command
+                .get_one::<bool>("reverse")
+                .expect("Argument always has a value");
+            let path = make_events_path_from_ip(
+                address,
+                history_command
+                    .get_one::<String>("since")
+                    .map(String::as_str),
+                history_command
+                    .get_one::<String>("until")
+                    .map(String::as_str),
+                reversed,
+            );
+            let ndjson = history_command
+                .get_one::<String>("format")
+                .map(String::as_str)
+                == Some("ndjson");
+            let pages = paged(&client, &path, no_paging)
+                .next_cursor(history::get_cursor_from_events_response)
+                .pages();
+            for_each_page(pages, |json_response| {
+                let Some(events) = json_response["result"]["events"].as_array() else {
+                    eprintln!("{}", json_response);
+                    process::exit(1);
+                };
+                for event in events {
+                    if ndjson {
+                        write_to_file_or_std_out(event, out_file);
+                    } else {
+                        write_to_file_or_std_out(&history::format_event(event), out_file);
+                    }
+                }
+            });
+        }
+        Some(("names", names_command)) => {
+            let mut addresses = Vec::new();
+            for address in names_command
+                .get_many::<String>("addresses")
+                .expect("Argument is required")
+            {
+                if address == "-" {
+                    addresses.extend(read_lines("-"));
+                } else {
+                    addresses.push(address.to_owned());
+                }
+            }
+            let show_address = addresses.len() > 1;
+            let mut seen = HashSet::new();
+            for address in &addresses {
+                let path = make_names_path_from_ip(address);
+                for_each_page(paged(&client, &path, no_paging).pages(), |json_response| {
+                    let Some(names) = json_response["result"]["names"].as_array() else {
+                        eprintln!("{}", json_response);
+                        process::exit(1);
+                    };
+                    for name in names.iter().filter_map(Value::as_str) {
+                        if !seen.insert((address.as_str(), name.to_owned())) {
+                            continue;
+                        }
+                        if show_address {
+                            write_to_file_or_std_out(&format!("{}\t{}", address, name), out_file);
+                        } else {
+                            write_to_file_or_std_out(name, out_file);
+                        }
+                    }
+                });
+            }
+        }
+        Some(("dns", dns_command)) => {
+            let mut results = results_or_exit(dns_command, out_file);
+            match dns_command.get_one::<String>("input") {
+                Some(input) => {
+                    let items = read_lines(input)
+                        .into_iter()
+                        .map(|dns_name| {
+                            let path = make_path_from_query(&format!("dns.names: {}", dns_name));
+                            (dns_name, path)
+                        })
+                        .collect();
+                    run_batch(&client, "dns", items, no_paging, concurrency, &mut results);
+                }
+                None => {
+                    let dns_name = dns_command
+                        .get_one::<String>("dns_name")
+                        .expect("Argument is required");
+                    let query = format!("dns.names: {}", dns_name);
+                    let path = make_path_from_query(&query);
+                    let pages = paged(&client, &path, no_paging).pages();
+                    write_host_pages(pages, "dns", dns_name, &mut results);
+                }
+            }
+        }
+        Some(("asn", asn_command)) => {
+            let mut results = results_or_exit(asn_command, out_file);
+            match asn_command.get_one::<String>("input") {
+                Some(input) => {
+                    let items = read_lines(input)
+                        .into_iter()
+                        .map(|asn| {
+                            let path =
+                                make_path_from_query(&format!("autonomous_system.asn: {}", asn));
+                            (asn, path)
+                        })
+                        .collect();
+                    run_batch(&client, "asn", items, no_paging, concurrency, &mut results);
+                }
+                None => {
+                    let asn = asn_command
+                        .get_one::<String>("asn")
+                        .expect("Argument is required");
+                    let query = format!("autonomous_system.asn: {}", asn);
+                    let path = make_path_from_query(&query);
+                    let pages = paged(&client, &path, no_paging).pages();
+                    write_host_pages(pages, "asn", asn, &mut results);
+                }
+            }
+        }
+        Some(("batch", batch_command)) => {
+            let input = batch_command
+                .get_one::<String>("input")
+                .expect("Argument is required");
+            let items = read_lines(input)
+                .into_iter()
+                .map(|query| {
+                    let path = make_path_from_query(&query);
+                    (query, path)
+                })
+                .collect();
+            let mut results = results_or_exit(batch_command, out_file);
+            run_batch(
+                &client,
+                "batch",
+                items,
+                no_paging,
+                concurrency,
+                &mut results,
+            );
+        }
+        Some(("cert", cert_command)) => match cert_command.subcommand() {
+            Some(("search", search_command)) => {
+                let query = search_command
+                    .get_one::<String>("query")
+                    .expect("Argument is required");
+                let fields: Vec<&str> = search_command
+                    .get_many::<String>("fields")
+                    .map(|fields| fields.map(String::as_str).collect())
+                    .unwrap_or_default();
+                let path = make_path_from_cert_query(query, &fields);
+                output_response(&client, &path, no_paging, out_file);
+            }
+            Some(("bulk", bulk_command)) => {
+                let input = bulk_command
+                    .get_one::<String>("input")
+                    .expect("Argument is required");
+                let mut fingerprints = Vec::new();
+                let mut failed = Vec::new();
+                for line in read_lines(input) {
+                    match fingerprint::parse_fingerprint(&line) {
+                        Ok(fingerprint) if !fingerprints.contains(&fingerprint) => {
+                            fingerprints.push(fingerprint)
+                        }
+                        Ok(_) => {}
+                        Err(_) => failed.push(format!("{} (invalid fingerprint)", line)),
+                    }
+                }
+                let requested = fingerprints.len() + failed.len();
+                let mut errors = Vec::new();
+                for batch in fingerprints.chunks(BULK_BATCH_SIZE) {
+                    for (fingerprint, record) in lookup_certificates(&client, batch) {
+                        match record {
+                            Ok(Some(record)) => write_to_file_or_std_out(&record, out_file),
+                            Ok(None) => failed.push(format!("{} (not found)", fingerprint)),
+                            Err(err) => errors.push(format!("{} ({})", fingerprint, err)),
+                        }
+                    }
+                }
+                if !failed.is_empty() {
+                    eprintln!(
+                        "{} of {} certificates could not be retrieved:",
+                        failed.len(),
+                        requested
+                    );
+                    for failure in &failed {
+                        eprintln!("  {}", failure);
+                    }
+                }
+                if !errors.is_empty() {
+                    eprintln!(
+                        "{} of {} lookups failed with an API error:",
+                        errors.len(),
+                        requested
+                    );
+                    for error in &errors {
+                        eprintln!("  {}", error);
+                    }
+                    process::exit(1);
+                }
+                if !failed.is_empty() {
+                    process::exit(2);
+                }
+            }
+            Some(("view", view_command)) => {
+                let fingerprint = get_fingerprint_or_exit(view_command);
+                let path = make_path_from_cert_fingerprint(&fingerprint);
+                let json_response =
+                    exit_on_api_error(client.send_request(Method::GET, &path, None));
+                match view_command.get_one::<String>("format").map(String::as_str) {
+                    Some("json") => write_to_file_or_std_out(&json_response, out_file),
+                    _ => {
+                        if !json_response["result"].is_object() {
+                            eprintln!("{}", json_response);
+                            process::exit(1);
+                        }
+                        let text = certificate::render_text(&json_response["result"]);
+                        write_to_file_or_std_out(&text, out_file);
+                    }
+                }
+            }
+            Some(("hosts", hosts_command)) => {
+                let fingerprint = get_fingerprint_or_exit(hosts_command);
+                let path = make_hosts_path_from_cert_fingerprint(&fingerprint);
+                output_response(&client, &path, no_paging, out_file);
+            }
+            Some(("comments", comments_command)) => {
+                let comments_path = |command: &ArgMatches| {
+                    make_comments_path_from_cert_fingerprint(&get_fingerprint_or_exit(command))
+                };
+                if comments_command.subcommand().is_some() {
+                    handle_comments_command(
+                        &client,
+                        comments_command,
+                        comments_path,
+                        no_paging,
+                        out_file,
+                    );
+                } else {
+                    let path = comments_path(comments_command);
+                    output_response(&client, &path, no_paging, out_file);
+                }
+            }
+            _ => unreachable!("All subcommands exhausted"),
+        },
+        Some(("tags", tags_command)) => {
+            handle_tags_command(&client, tags_command, no_paging, out_file)
+        }
+        _ => unreachable!("All subcommands exhausted"),
+    }
+}
+
+fn build_cli() -> Command {
+    Command::new("censys-search")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about(env!("CARGO_PKG_DESCRIPTION"))
         .subcommand_required(true)
         .arg_required_else_help(true)
         .arg(
@@ -26,197 +601,1117 @@ fn main() {
                 .required(false)
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            arg!(-c --concurrency <N> "Number of parallel requests when reading --input")
+                .required(false)
+                .value_parser(clap::value_parser!(u16).range(1..=64))
+                .default_value("1"),
+        )
+        .arg(
+            arg!(--rate_limit <PER_SECOND> "Maximum number of requests started per second across all workers")
+                .required(false)
+                .value_parser(clap::value_parser!(u32).range(1..)),
+        )
+        .arg(
+            arg!(--no_cache "Send every request instead of answering from the response cache")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            arg!(--refresh "Ignore cached responses, but cache the new ones")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .conflicts_with("no_cache"),
+        )
+        .arg(
+            arg!(--offline "Answer only from the response cache, however old, and never send requests")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["no_cache", "refresh"]),
+        )
+        .arg(
+            arg!(--record <CASSETTE> "Save every request and response to this file, with credentials scrubbed")
+                .required(false)
+                .conflicts_with("offline"),
+        )
+        .arg(
+            arg!(--replay <CASSETTE> "Answer requests from a file saved with --record instead of sending them")
+                .required(false)
+                .conflicts_with_all(["offline", "record"]),
+        )
+        .arg(
+            arg!(--cache_ttl <SECONDS> "Age after which cached responses are fetched again")
+                .required(false)
+                .value_parser(clap::value_parser!(u64))
+                .default_value("86400"),
+        )
+        .arg(
+            arg!(--cache_size <MB> "Size of the response cache, least recently used responses are evicted first")
+                .required(false)
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .default_value("100"),
+        )
         .subcommand(
             Command::new("query")
                 .about("Search based on custom query")
                 .arg_required_else_help(true)
-                .arg(arg!([query] "Query using the Censys Search query language").required(true)),
+                .arg(arg!([query] "Query using the Censys Search query language").required(true))
+                .arg(
+                    arg!(--check_quota "Abort if the estimated number of pages exceeds the remaining quota")
+                        .required(false)
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(results_format_arg())
+                .after_help(
+                    "Examples:\n  censys-search query 'services.port: 22'\n  \
+                     censys-search -n query 'location.country: Germany and services.service_name: HTTP'\n  \
+                     censys-search query --check_quota 'services.service_name: MODBUS'\n  \
+                     censys-search -o censys.db query 'services.port: 502' --format sqlite",
+                ),
+        )
+        .subcommand(
+            Command::new("account")
+                .visible_alias("whoami")
+                .about("Show the account login and query quota usage")
+                .arg(
+                    arg!(-f --format <FORMAT> "Output format")
+                        .required(false)
+                        .value_parser(["table", "json"])
+                        .default_value("table"),
+                )
+                .after_help("Examples:\n  censys-search account\n  censys-search whoami --format json"),
+        )
+        .subcommand(
+            Command::new("aggregate")
+                .about("Show the distribution of a field across hosts matching a query")
+                .arg_required_else_help(true)
+                .arg(arg!([query] "Query using the Censys Search query language").required(true))
+                .arg(
+                    arg!(-F --field <FIELD> "Field to aggregate on (see the fields subcommand)")
+                        .required(true)
+                        .value_parser(aggregate::parse_field),
+                )
+                .arg(
+                    arg!(-b --buckets <N> "Number of buckets to return")
+                        .required(false)
+                        .value_parser(clap::value_parser!(u16).range(1..=1000))
+                        .default_value("50"),
+                )
+                .arg(
+                    arg!(-f --format <FORMAT> "Output format")
+                        .required(false)
+                        .value_parser(["histogram", "csv", "json"])
+                        .default_value("histogram"),
+                )
+                .after_help(
+                    "Examples:\n  censys-search aggregate 'services.service_name: SSH' --field services.port\n  \
+                     censys-search aggregate 'services.port: 3389' --field location.country --buckets 10 --format csv",
+                ),
         )
         .subcommand(
             Command::new("ip")
                 .about("Search based on IP address")
                 .arg_required_else_help(true)
-                .arg(arg!([address] "IP address").required(true)),
+                .subcommand_negates_reqs(true)
+                .args_conflicts_with_subcommands(true)
+                .subcommand(
+                    Command::new("comments")
+                        .about("Manage comments on a host")
+                        .arg_required_else_help(true)
+                        .subcommand_required(true)
+                        .subcommands(comments_subcommands(&[
+                            arg!([address] "IP address").required(true)
+                        ]))
+                        .after_help(
+                            "Examples:\n  censys-search ip comments list 8.8.8.8\n  \
+                             censys-search ip comments add 8.8.8.8 --body 'Known resolver'\n  \
+                             censys-search ip comments update 8.8.8.8 --id 42 --body_file notes.md\n  \
+                             censys-search ip comments delete 8.8.8.8 --id 42",
+                        ),
+                )
+                .arg(arg!([address] "IP address").required_unless_present("input"))
+                .arg(input_arg("IP addresses", "address"))
+                .arg(results_format_arg())
+                .arg(
+                    arg!(-t --at_time <TIME> "View the host as it was at this time (RFC 3339, YYYY-MM-DD or relative such as -30d)")
+                        .required(false)
+                        .allow_hyphen_values(true)
+                        .value_parser(timestamp::parse_timestamp),
+                )
+                .after_help(
+                    "Examples:\n  censys-search ip 8.8.8.8\n  \
+                     censys-search ip 8.8.8.8 --at_time 2022-12-01T00:00:00Z\n  \
+                     censys-search ip 8.8.8.8 --at_time -30d\n  \
+                     censys-search ip --input addresses.txt",
+                ),
+        )
+        .subcommand(
+            Command::new("diff")
+                .about("Compare two snapshots of a host")
+                .arg_required_else_help(true)
+                .arg(arg!([address] "IP address of the first host").required(true))
+                .arg(
+                    arg!(-b --ip_b <IP> "IP address of the second host (defaults to the first)")
+                        .required(false),
+                )
+                .arg(
+                    arg!(--at_time_a <TIME> "Time of the first snapshot (defaults to now)")
+                        .required(false)
+                        .allow_hyphen_values(true)
+                        .value_parser(timestamp::parse_timestamp),
+                )
+                .arg(
+                    arg!(--at_time_b <TIME> "Time of the second snapshot (defaults to now)")
+                        .required(false)
+                        .allow_hyphen_values(true)
+                        .value_parser(timestamp::parse_timestamp),
+                )
+                .arg(
+                    arg!(-f --format <FORMAT> "Output format")
+                        .required(false)
+                        .value_parser(["summary", "json"])
+                        .default_value("summary"),
+                )
+                .after_help(
+                    "Examples:\n  censys-search diff 8.8.8.8 --at_time_a -30d\n  \
+                     censys-search diff 8.8.8.8 --at_time_a 2022-11-01 --at_time_b 2022-12-01\n  \
+                     censys-search diff 8.8.8.8 --ip_b 8.8.4.4 --format json",
+                ),
+        )
+        .subcommand(
+            Command::new("history")
+                .about("Show the timeline of events observed on a host")
+                .arg_required_else_help(true)
+                .arg(arg!([address] "IP address").required(true))
+                .arg(
+                    arg!(--since <TIME> "Only show events after this time (RFC 3339, YYYY-MM-DD or relative such as -30d)")
+                        .required(false)
+                        .allow_hyphen_values(true)
+                        .value_parser(timestamp::parse_timestamp),
+                )
+                .arg(
+                    arg!(--until <TIME> "Only show events before this time (RFC 3339, YYYY-MM-DD or relative such as -30d)")
+                        .required(false)
+                        .allow_hyphen_values(true)
+                        .value_parser(timestamp::parse_timestamp),
+                )
+                .arg(
+                    arg!(-r --reverse "Show the newest events first")
+                        .required(false)
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    arg!(-f --format <FORMAT> "Output format")
+                        .required(false)
+                        .value_parser(["table", "ndjson"])
+                        .default_value("table"),
+                )
+                .after_help(
+                    "Examples:\n  censys-search history 8.8.8.8 --since -90d\n  \
+                     censys-search history 8.8.8.8 --since 2022-01-01 --until 2022-02-01 --format ndjson",
+                ),
+        )
+        .subcommand(
+            Command::new("names")
+                .about("List the DNS names observed for IP addresses")
+                .arg_required_else_help(true)
+                .arg(
+                    arg!([addresses] ... "IP addresses (use - to read one per line from stdin)")
+                        .required(true),
+                )
+                .after_help(
+                    "Names are deduplicated. When more than one address is given, each line\n\
+                     is prefixed with the address and a tab.\n\n\
+                     Examples:\n  censys-search names 8.8.8.8\n  \
+                     cut -f1 hosts.tsv | censys-search names -",
+                ),
         )
         .subcommand(
             Command::new("dns")
                 .about("Search based on DNS name")
                 .arg_required_else_help(true)
-                .arg(arg!([dns_name] "DNS name").required(true)),
+                .arg(arg!([dns_name] "DNS name").required_unless_present("input"))
+                .arg(input_arg("DNS names", "dns_name"))
+                .arg(results_format_arg())
+                .after_help(
+                    "Examples:\n  censys-search dns example.com\n  \
+                     censys-search dns --input - < domains.txt",
+                ),
         )
         .subcommand(
             Command::new("asn")
                 .about("Search based on autonomous system number")
                 .arg_required_else_help(true)
-                .arg(arg!([asn] "Autonomous system number").required(true)),
+                .arg(arg!([asn] "Autonomous system number").required_unless_present("input"))
+                .arg(input_arg("autonomous system numbers", "asn"))
+                .arg(results_format_arg())
+                .after_help(
+                    "Examples:\n  censys-search -o hits.json asn 13335\n  \
+                     censys-search asn --input asns.txt",
+                ),
+        )
+        .subcommand(
+            Command::new("batch")
+                .about("Run every query in a file")
+                .arg_required_else_help(true)
+                .arg(
+                    arg!(--input <FILE> "File with one query per line (use - for stdin)")
+                        .required(true),
+                )
+                .arg(results_format_arg())
+                .after_help(
+                    "Each output record is wrapped as {\"input\": ..., \"response\": ...}. Inputs\n\
+                     that fail are listed on stderr and the exit status is 2.\n\n\
+                     Examples:\n  censys-search batch --input queries.txt\n  \
+                     censys-search -o censys.db batch --input queries.txt --format sqlite",
+                ),
         )
         .subcommand(
             Command::new("cert")
                 .about("Search based on TLS certificate")
                 .arg_required_else_help(true)
                 .subcommand(
-                    Command::new("hosts")
-                        .about("Search for hosts related to the certificate")
+                    Command::new("search")
+                        .about("Search the certificates index")
+                        .arg_required_else_help(true)
+                        .arg(
+                            arg!([query] "Query using the Censys Search query language")
+                                .required(true),
+                        )
+                        .arg(
+                            arg!(-F --fields <FIELDS> "Comma separated certificate fields to return")
+                                .required(false)
+                                .value_delimiter(','),
+                        )
+                        .after_help(
+                            "Examples:\n  censys-search cert search 'parsed.subject.common_name: example.com'\n  \
+                             censys-search cert search 'parsed.issuer.organization: DigiCert' \
+                             --fields fingerprint_sha256,parsed.subject_dn",
+                        ),
+                )
+                .subcommand(
+                    Command::new("bulk")
+                        .about("Look up many certificates from a list of fingerprints")
                         .arg_required_else_help(true)
                         .arg(
-                            arg!([fingerprint] "SHA256 fingerprint of the certificate")
+                            arg!(--input <FILE> "File with one SHA256 fingerprint per line (use - for stdin)")
                                 .required(true),
+                        )
+                        .after_help(
+                            "One record is written per certificate found. Fingerprints that are\n\
+                             invalid or not found are listed on stderr and the exit status is 2.\n\n\
+                             Examples:\n  censys-search cert bulk --input fingerprints.txt\n  \
+                             zeek-cut cert_chain_fps < ssl.log | tr , '\\n' | censys-search cert bulk --input -",
+                        ),
+                )
+                .subcommand(
+                    Command::new("view")
+                        .about("Show the full certificate record")
+                        .arg_required_else_help(true)
+                        .args(fingerprint_args())
+                        .arg(
+                            arg!(-f --format <FORMAT> "Output format")
+                                .required(false)
+                                .value_parser(["table", "json"])
+                                .default_value("table"),
+                        )
+                        .after_help(
+                            "Examples:\n  censys-search cert view \
+                             fb444eb8e68437bae06232b9f5091bccff62a768ca09e92eb5c9c2cf9d17c426\n  \
+                             censys-search cert view --format json \
+                             fb444eb8e68437bae06232b9f5091bccff62a768ca09e92eb5c9c2cf9d17c426",
+                        ),
+                )
+                .subcommand(
+                    Command::new("hosts")
+                        .about("Search for hosts related to the certificate")
+                        .arg_required_else_help(true)
+                        .args(fingerprint_args())
+                        .after_help(
+                            "Examples:\n  censys-search cert hosts \
+                             fb444eb8e68437bae06232b9f5091bccff62a768ca09e92eb5c9c2cf9d17c426\n  \
+                             censys-search cert hosts --file server.pem",
                         ),
                 )
                 .subcommand(
                     Command::new("comments")
                         .about("Search for comments related to the certificate")
                         .arg_required_else_help(true)
+                        .subcommand_negates_reqs(true)
+                        .args_conflicts_with_subcommands(true)
+                        .args(fingerprint_args())
+                        .subcommands(comments_subcommands(&fingerprint_args()))
+                        .after_help(
+                            "Examples:\n  censys-search cert comments \
+                             fb444eb8e68437bae06232b9f5091bccff62a768ca09e92eb5c9c2cf9d17c426\n  \
+                             censys-search cert comments add --file server.pem --body 'Phishing kit'\n  \
+                             censys-search cert comments delete --file server.pem --id 42",
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("tags")
+                .about("Manage tags on hosts and certificates")
+                .arg_required_else_help(true)
+                .subcommand_required(true)
+                .subcommand(Command::new("list").about("List all tags"))
+                .subcommand(
+                    Command::new("create")
+                        .about("Create a tag")
+                        .arg_required_else_help(true)
+                        .arg(arg!([name] "Name of the tag").required(true))
+                        .args(tag_metadata_args()),
+                )
+                .subcommand(
+                    Command::new("update")
+                        .about("Rename a tag or change its metadata")
+                        .arg_required_else_help(true)
+                        .arg(arg!([tag_id] "ID of the tag").required(true))
+                        .arg(arg!(--name <NAME> "New name of the tag").required(false))
+                        .args(tag_metadata_args()),
+                )
+                .subcommand(
+                    Command::new("delete")
+                        .about("Delete a tag")
+                        .arg_required_else_help(true)
+                        .arg(arg!([tag_id] "ID of the tag").required(true))
                         .arg(
-                            arg!([fingerprint] "SHA256 fingerprint of the certificate")
-                                .required(true),
+                            arg!(-y --yes "Do not ask for confirmation")
+                                .required(false)
+                                .action(ArgAction::SetTrue),
                         ),
+                )
+                .subcommand(
+                    Command::new("tag")
+                        .about("Add a tag to a host or certificate")
+                        .arg_required_else_help(true)
+                        .arg(arg!([tag_id] "ID of the tag").required(true))
+                        .args(tag_target_args()),
+                )
+                .subcommand(
+                    Command::new("untag")
+                        .about("Remove a tag from a host or certificate")
+                        .arg_required_else_help(true)
+                        .arg(arg!([tag_id] "ID of the tag").required(true))
+                        .args(tag_target_args()),
+                )
+                .subcommand(
+                    Command::new("hosts")
+                        .about("List hosts carrying a tag")
+                        .arg_required_else_help(true)
+                        .arg(arg!([tag_id] "ID of the tag").required(true)),
+                )
+                .subcommand(
+                    Command::new("certs")
+                        .about("List certificates carrying a tag")
+                        .arg_required_else_help(true)
+                        .arg(arg!([tag_id] "ID of the tag").required(true)),
+                )
+                .after_help(
+                    "Examples:\n  censys-search tags create perimeter --description 'Our own hosts'\n  \
+                     censys-search tags tag 7 --ip 203.0.113.10\n  \
+                     censys-search tags untag 7 --cert \
+                     fb444eb8e68437bae06232b9f5091bccff62a768ca09e92eb5c9c2cf9d17c426\n  \
+                     censys-search tags hosts 7",
+                ),
+        )
+        .subcommand(
+            Command::new("local")
+                .about("Search saved host records with the query language, without the API")
+                .arg_required_else_help(true)
+                .arg(
+                    arg!([query] "Query using the Censys Search query language")
+                        .required(true)
+                        .value_parser(Query::parse),
+                )
+                .arg(
+                    arg!(--data <FILE> "Saved hosts, search pages or batch output, one JSON object per line (use - for stdin)")
+                        .required(true),
+                )
+                .arg(
+                    arg!(--count "Print the number of matching hosts instead of the hosts")
+                        .required(false)
+                        .action(ArgAction::SetTrue),
+                )
+                .after_help(
+                    "Examples:\n  censys-search -o pages.json query 'services.service_name: SSH'\n  \
+                     censys-search local 'location.country_code: DE and services.port: [20 to 25]' --data pages.json\n  \
+                     censys-search local 'services: (port: 443 and not service_name: HTTP)' --data pages.json --count\n  \
+                     censys-search local 'dns.names: *.example.com' --data - < hits.ndjson",
+                ),
+        )
+        .subcommand(
+            Command::new("fields")
+                .about("Show all available Censys Search query language fields")
+                .after_help("Examples:\n  censys-search fields | grep services.tls"),
+        )
+        .subcommand(
+            Command::new("cache")
+                .about("Inspect or clean up the local response cache")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("stats").about("Show the number and size of cached responses"),
+                )
+                .subcommand(Command::new("clear").about("Remove every cached response"))
+                .subcommand(
+                    Command::new("prune")
+                        .about("Remove expired responses and evict any over --cache_size"),
+                )
+                .after_help(
+                    "Responses are stored in CENSYS_CACHE_DIR, or in censys-search under \
+                     XDG_CACHE_HOME or ~/.cache.\n\n\
+                     Examples:\n  censys-search cache stats\n  \
+                     censys-search --cache_ttl 3600 cache prune\n  \
+                     censys-search cache clear",
                 ),
         )
         .subcommand(
-            Command::new("fields").about("Show all available Censys Search query language fields"),
+            Command::new("man")
+                .about("Generate man pages or a Markdown reference for this utility")
+                .arg(
+                    arg!(-d --out_dir <DIR> "Write one man page per command, or a single Markdown reference, to this directory")
+                        .required(false),
+                )
+                .arg(
+                    arg!(-m --markdown "Render a Markdown reference instead of roff")
+                        .required(false)
+                        .action(ArgAction::SetTrue),
+                )
+                .after_help(
+                    "Examples:\n  censys-search man | man -l -\n  \
+                     censys-search man --out_dir /usr/local/share/man/man1\n  \
+                     censys-search man --markdown > docs/cli.md",
+                ),
         )
-        .get_matches();
+}
 
-    let api_id = match arg_matches.get_one::<String>("api_id") {
-        Some(value) => value.to_owned(),
-        None => get_env_or_exit("CENSYS_API_ID"),
-    };
-    let secret = match arg_matches.get_one::<String>("secret") {
-        Some(value) => value.to_owned(),
-        None => get_env_or_exit("CENSYS_SECRET"),
+fn comments_subcommands(target: &[Arg]) -> [Command; 4] {
+    let id = arg!(--id <ID> "ID of the comment")
+        .required(true)
+        .value_parser(clap::value_parser!(u64));
+    let contents = [
+        arg!(--body <TEXT> "Text of the comment").required(false),
+        arg!(--body_file <FILE> "Read the text of the comment from a file (use - for stdin)")
+            .required(false)
+            .conflicts_with("body"),
+    ];
+    let contents_group = ArgGroup::new("contents")
+        .args(["body", "body_file"])
+        .required(true);
+    [
+        Command::new("list")
+            .about("List comments")
+            .arg_required_else_help(true)
+            .args(target),
+        Command::new("add")
+            .about("Add a comment")
+            .arg_required_else_help(true)
+            .args(target)
+            .args(&contents)
+            .group(contents_group.clone()),
+        Command::new("update")
+            .about("Replace the text of a comment")
+            .arg_required_else_help(true)
+            .args(target)
+            .arg(&id)
+            .args(&contents)
+            .group(contents_group),
+        Command::new("delete")
+            .about("Delete a comment")
+            .arg_required_else_help(true)
+            .args(target)
+            .arg(&id)
+            .arg(
+                arg!(-y --yes "Do not ask for confirmation")
+                    .required(false)
+                    .action(ArgAction::SetTrue),
+            ),
+    ]
+}
+
+fn results_format_arg() -> Arg {
+    arg!(-f --format <FORMAT> "Output format (sqlite upserts hosts into the --output database)")
+        .required(false)
+        .value_parser(["json", "sqlite"])
+        .default_value("json")
+}
+
+fn input_arg(items: &str, positional: &'static str) -> Arg {
+    Arg::new("input")
+        .long("input")
+        .value_name("FILE")
+        .help(format!(
+            "Read {} from a file, one per line (use - for stdin)",
+            items
+        ))
+        .conflicts_with(positional)
+}
+
+fn tag_metadata_args() -> [Arg; 2] {
+    [
+        arg!(--description <TEXT> "Description of the tag").required(false),
+        arg!(--color <COLOR> "Hex color of the tag, e.g. #ff0000").required(false),
+    ]
+}
+
+fn tag_target_args() -> [Arg; 2] {
+    [
+        arg!(--ip <IP> "IP address of the host")
+            .required_unless_present("cert")
+            .conflicts_with("cert"),
+        arg!(--cert <FINGERPRINT> "SHA256 fingerprint of the certificate")
+            .required(false)
+            .value_parser(fingerprint::parse_fingerprint),
+    ]
+}
+
+fn fingerprint_args() -> [Arg; 2] {
+    [
+        arg!([fingerprint] "SHA256 fingerprint of the certificate (colons and case are ignored)")
+            .required_unless_present("file")
+            .value_parser(fingerprint::parse_fingerprint),
+        arg!(--file <FILE> "PEM or DER encoded certificate to fingerprint locally")
+            .required(false)
+            .conflicts_with("fingerprint"),
+    ]
+}
+
+/// Fetches records for a batch of fingerprints, preferring the bulk endpoint
+/// and falling back to one view per fingerprint on servers without it. Each
+/// record is `None` when the certificate was not found.
+fn lookup_certificates(
+    client: &CensysClient,
+    fingerprints: &[String],
+) -> Vec<(String, Result<Option<Value>, String>)> {
+    let path = make_bulk_path_from_cert_fingerprints(fingerprints);
+    let json_response = match client.send_request(Method::GET, &path, None) {
+        Err(Error::Api(404 | 405, _)) => {
+            return fingerprints
+                .iter()
+                .map(|fingerprint| {
+                    (
+                        fingerprint.to_owned(),
+                        lookup_certificate(client, fingerprint),
+                    )
+                })
+                .collect()
+        }
+        Ok(json_response) if json_response["result"].is_array() => Ok(json_response),
+        Ok(json_response) => Err(Error::UnexpectedResponse(json_response).to_string()),
+        Err(err) => Err(err.to_string()),
     };
-    let no_paging = *arg_matches
-        .get_one::<bool>("no_paging")
-        .expect("Argument always has a value");
-    let out_file = arg_matches.get_one::<String>("output").map(Path::new);
-    let token = base64::encode(format!("{}:{}", api_id, secret));
-    let client = Client::new();
+    fingerprints
+        .iter()
+        .map(|fingerprint| {
+            let record = match &json_response {
+                Ok(json_response) => Ok(json_response["result"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .find(|record| record["fingerprint_sha256"] == fingerprint.as_str())
+                    .cloned()),
+                // Every fingerprint in the batch shares the same failure.
+                Err(err) => Err(err.to_owned()),
+            };
+            (fingerprint.to_owned(), record)
+        })
+        .collect()
+}
 
-    match arg_matches.subcommand() {
-        Some(("query", query_command)) => {
-            let query = query_command
-                .get_one::<String>("query")
-                .expect("Argument is required");
-            let path = make_path_from_query(query);
-            output_response(&client, &token, &path, no_paging, out_file);
+fn lookup_certificate(client: &CensysClient, fingerprint: &str) -> Result<Option<Value>, String> {
+    let path = make_path_from_cert_fingerprint(fingerprint);
+    match client.send_request(Method::GET, &path, None) {
+        Ok(json_response) if json_response["result"].is_object() => {
+            Ok(Some(json_response["result"].to_owned()))
         }
-        Some(("ip", ip_command)) => {
-            let address = ip_command
-                .get_one::<String>("address")
-                .expect("Argument is required");
-            let path = make_path_from_ip(address);
-            output_response(&client, &token, &path, no_paging, out_file);
+        Ok(json_response) => Err(Error::UnexpectedResponse(json_response).to_string()),
+        Err(Error::Api(404, _)) => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn handle_comments_command(
+    client: &CensysClient,
+    comments_command: &ArgMatches,
+    comments_path: impl Fn(&ArgMatches) -> String,
+    no_paging: bool,
+    out_file: Option<&Path>,
+) {
+    let (action, action_command) = comments_command
+        .subcommand()
+        .expect("Subcommand is required");
+    let path = comments_path(action_command);
+    let comment_path = || {
+        let id = action_command
+            .get_one::<u64>("id")
+            .expect("Argument is required");
+        format!("{}/{}", path, id)
+    };
+    let json_response = match action {
+        "list" => {
+            output_response(client, &path, no_paging, out_file);
+            return;
         }
-        Some(("dns", dns_command)) => {
-            let dns_name = dns_command
-                .get_one::<String>("dns_name")
-                .expect("Argument is required");
-            let query = format!("dns.names: {}", dns_name);
-            let path = make_path_from_query(&query);
-            output_response(&client, &token, &path, no_paging, out_file);
+        "add" => {
+            let body = json!({ "contents": get_comment_contents_or_exit(action_command) });
+            exit_on_api_error(client.send_request(Method::POST, &path, Some(&body)))
         }
-        Some(("asn", asn_command)) => {
-            let asn = asn_command
-                .get_one::<String>("asn")
+        "update" => {
+            let body = json!({ "contents": get_comment_contents_or_exit(action_command) });
+            exit_on_api_error(client.send_request(Method::PUT, &comment_path(), Some(&body)))
+        }
+        "delete" => {
+            let comment_path = comment_path();
+            let yes = *action_command
+                .get_one::<bool>("yes")
+                .expect("Argument always has a value");
+            if !yes && !confirm(&format!("Delete comment {}?", comment_path)) {
+                eprintln!("Aborted");
+                process::exit(1);
+            }
+            exit_on_api_error(client.send_request(Method::DELETE, &comment_path, None))
+        }
+        _ => unreachable!("All subcommands exhausted"),
+    };
+    write_to_file_or_std_out(&json_response, out_file);
+}
+
+fn handle_tags_command(
+    client: &CensysClient,
+    tags_command: &ArgMatches,
+    no_paging: bool,
+    out_file: Option<&Path>,
+) {
+    let (action, action_command) = tags_command.subcommand().expect("Subcommand is required");
+    let tag_path = || {
+        let tag_id = action_command
+            .get_one::<String>("tag_id")
+            .expect("Argument is required");
+        make_path_from_tag_id(tag_id)
+    };
+    let target_path = || {
+        let tag_id = action_command
+            .get_one::<String>("tag_id")
+            .expect("Argument is required");
+        match action_command.get_one::<String>("ip") {
+            Some(ip) => make_tag_path_from_ip(ip, tag_id),
+            None => {
+                let fingerprint = action_command
+                    .get_one::<String>("cert")
+                    .expect("Either ip or cert is required");
+                make_tag_path_from_cert_fingerprint(fingerprint, tag_id)
+            }
+        }
+    };
+    let result = match action {
+        "list" => client.send_request(Method::GET, "/tags", None),
+        "create" => {
+            let name = action_command
+                .get_one::<String>("name")
                 .expect("Argument is required");
-            let query = format!("autonomous_system.asn: {}", asn);
-            let path = make_path_from_query(&query);
-            output_response(&client, &token, &path, no_paging, out_file);
+            let body = json!({
+                "name": name,
+                "metadata": get_tag_metadata(action_command, &Value::Null),
+            });
+            client.send_request(Method::POST, "/tags", Some(&body))
         }
-        Some(("cert", cert_command)) => match cert_command.subcommand() {
-            Some(("hosts", hosts_command)) => {
-                let fingerprint = hosts_command
-                    .get_one::<String>("fingerprint")
-                    .expect("Argument is required");
-                let path = make_hosts_path_from_cert_fingerprint(fingerprint);
-                output_response(&client, &token, &path, no_paging, out_file);
+        "update" => {
+            // The API replaces the whole tag, so unchanged fields are carried over.
+            let current_response = send_request(client, &tag_path());
+            let current = &current_response["result"];
+            if !current.is_object() {
+                eprintln!("{}", current_response);
+                process::exit(1);
             }
-            Some(("comments", comments_command)) => {
-                let fingerprint = comments_command
-                    .get_one::<String>("fingerprint")
-                    .expect("Argument is required");
-                let path = make_comments_path_from_cert_fingerprint(fingerprint);
-                output_response(&client, &token, &path, no_paging, out_file);
+            let name = action_command
+                .get_one::<String>("name")
+                .map(String::as_str)
+                .or_else(|| current["name"].as_str());
+            let body = json!({
+                "name": name,
+                "metadata": get_tag_metadata(action_command, &current["metadata"]),
+            });
+            client.send_request(Method::PUT, &tag_path(), Some(&body))
+        }
+        "delete" => {
+            let yes = *action_command
+                .get_one::<bool>("yes")
+                .expect("Argument always has a value");
+            if !yes && !confirm(&format!("Delete tag {}?", tag_path())) {
+                eprintln!("Aborted");
+                process::exit(1);
             }
-            _ => unreachable!("All subcommands exhausted"),
-        },
-        Some(("fields", _)) => {
-            println!("{}", constants::CENSYS_SEARCH_FIELDS);
+            client.send_request(Method::DELETE, &tag_path(), None)
+        }
+        "tag" => client.send_request(Method::PUT, &target_path(), None),
+        "untag" => client.send_request(Method::DELETE, &target_path(), None),
+        "hosts" => {
+            let path = format!("{}/hosts", tag_path());
+            output_response(client, &path, no_paging, out_file);
+            return;
+        }
+        "certs" => {
+            let path = format!("{}/certificates", tag_path());
+            output_response(client, &path, no_paging, out_file);
+            return;
         }
         _ => unreachable!("All subcommands exhausted"),
+    };
+    write_to_file_or_std_out(&exit_on_api_error(result), out_file);
+}
+
+fn get_tag_metadata(command: &ArgMatches, current: &Value) -> Value {
+    let mut metadata = match current {
+        Value::Object(metadata) => metadata.to_owned(),
+        _ => serde_json::Map::new(),
+    };
+    for key in ["description", "color"] {
+        if let Some(value) = command.get_one::<String>(key) {
+            metadata.insert(key.to_owned(), Value::String(value.to_owned()));
+        }
     }
+    Value::Object(metadata)
 }
 
-fn output_response(
-    client: &Client,
-    token: &str,
-    path: &str,
+fn get_comment_contents_or_exit(command: &ArgMatches) -> String {
+    if let Some(body) = command.get_one::<String>("body") {
+        return body.to_owned();
+    }
+    let body_file = command
+        .get_one::<String>("body_file")
+        .expect("Either body or body_file is required");
+    read_to_string(body_file).trim_end().to_owned()
+}
+
+fn confirm(prompt: &str) -> bool {
+    eprint!("{} [y/N] ", prompt);
+    io::stderr().flush().expect("Unable to write to stderr");
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .expect("Unable to read from stdin");
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+fn output_response(client: &CensysClient, path: &str, no_paging: bool, out_file: Option<&Path>) {
+    for_each_page(paged(client, path, no_paging).pages(), |json_response| {
+        write_to_file_or_std_out(json_response, out_file)
+    });
+}
+
+/// Where the host results of query, ip, dns, asn and batch are written.
+enum Results<'a> {
+    /// One JSON response per line, to stdout or the --output file.
+    Json(Option<&'a Path>),
+    Sqlite(sqlite::Database),
+}
+
+fn results_or_exit<'a>(command: &ArgMatches, out_file: Option<&'a Path>) -> Results<'a> {
+    match command.get_one::<String>("format").map(String::as_str) {
+        Some("sqlite") => {
+            let Some(path) = out_file else {
+                eprintln!("--format sqlite needs a database file to write to, set --output");
+                process::exit(1);
+            };
+            match sqlite::Database::open(path) {
+                Ok(database) => Results::Sqlite(database),
+                Err(err) => {
+                    eprintln!("Unable to open {}: {}", path.display(), err);
+                    process::exit(1);
+                }
+            }
+        }
+        _ => Results::Json(out_file),
+    }
+}
+
+/// Writes every page, or upserts its hosts as a new run of `command` for
+/// `input` when writing to SQLite.
+fn write_host_pages(pages: Pages, command: &str, input: &str, results: &mut Results) {
+    match results {
+        Results::Json(out_file) => for_each_page(pages, |json_response| {
+            write_to_file_or_std_out(json_response, *out_file)
+        }),
+        Results::Sqlite(database) => {
+            let run_id = exit_on_database_error(database.start_run(command, input));
+            let mut page = 0;
+            for_each_page(pages, |json_response| {
+                page += 1;
+                exit_on_database_error(database.insert_page(run_id, page, json_response));
+            });
+        }
+    }
+}
+
+fn exit_on_database_error<T>(result: rusqlite::Result<T>) -> T {
+    result.unwrap_or_else(|err| {
+        eprintln!("Unable to write to the database: {}", err);
+        process::exit(1);
+    })
+}
+
+fn paged<'a>(client: &'a CensysClient, path: &str, no_paging: bool) -> Search<'a> {
+    let search = client.paged(path.to_owned());
+    if no_paging {
+        search.max_pages(1)
+    } else {
+        search
+    }
+}
+
+fn for_each_page(pages: Pages, mut handle_page: impl FnMut(&Value)) {
+    for page in pages {
+        match page {
+            Ok(json_response) => handle_page(&json_response),
+            Err(err) => {
+                eprintln!("{}", err);
+                process::exit(1);
+            }
+        }
+    }
+}
+
+/// Runs every (input, path) item, tagging each output record with its input
+/// and reporting failed items at the end instead of stopping at the first.
+/// Records are written in input order regardless of `concurrency`. Each input
+/// is a separate run in a SQLite database.
+fn run_batch(
+    client: &CensysClient,
+    command: &str,
+    items: Vec<(String, String)>,
     no_paging: bool,
-    out_file: Option<&Path>,
+    concurrency: usize,
+    results: &mut Results,
 ) {
-    let mut json_response = send_request(client, token, path);
-    write_to_file_or_std_out(&json_response, out_file);
-    if no_paging {
-        return;
+    let mut failures = Vec::new();
+    pool::run_ordered(
+        &items,
+        concurrency,
+        |(_, path)| {
+            paged(client, path, no_paging)
+                .pages()
+                .collect::<Result<Vec<_>, _>>()
+        },
+        |(input, _), result| match (result, &mut *results) {
+            (Ok(pages), Results::Json(out_file)) => {
+                for json_response in pages {
+                    let record = json!({ "input": input, "response": json_response });
+                    write_to_file_or_std_out(&record, *out_file);
+                }
+            }
+            (Ok(pages), Results::Sqlite(database)) => {
+                let run_id = exit_on_database_error(database.start_run(command, input));
+                for (index, json_response) in pages.iter().enumerate() {
+                    exit_on_database_error(database.insert_page(run_id, index + 1, json_response));
+                }
+            }
+            (Err(err), _) => failures.push(format!("{}: {}", input, err)),
+        },
+    );
+    if !failures.is_empty() {
+        eprintln!("{} of {} inputs failed:", failures.len(), items.len());
+        for failure in &failures {
+            eprintln!("  {}", failure);
+        }
+        process::exit(2);
+    }
+}
+
+/// Fetches the first page of a search and exits unless the remaining query
+/// allowance covers every page the search is expected to take.
+fn check_quota_or_exit(client: &CensysClient, path: &str, no_paging: bool) -> Value {
+    let account = exit_unless_api_response(client.account());
+    let quota = &account["quota"];
+    let (Some(used), Some(allowance)) = (quota["used"].as_u64(), quota["allowance"].as_u64())
+    else {
+        eprintln!("{}", account);
+        process::exit(1);
+    };
+    let remaining = allowance.saturating_sub(used);
+    if remaining == 0 {
+        eprintln!("No queries remain in the current quota");
+        process::exit(1);
+    }
+    let first_page = send_request(client, path);
+    let total = first_page["result"]["total"].as_u64().unwrap_or(0);
+    let per_page = first_page["result"]["hits"]
+        .as_array()
+        .map_or(0, Vec::len)
+        .max(1) as u64;
+    let pages = if no_paging {
+        1
+    } else {
+        total.div_ceil(per_page).max(1)
+    };
+    if pages > remaining {
+        eprintln!(
+            "Query needs about {} pages for {} hits but only {} queries remain, aborting",
+            pages, total, remaining
+        );
+        process::exit(1);
     }
-    let mut cursor = get_cursor_from_response(&json_response);
-    while cursor.is_some() {
-        let path = format!("{}&cursor={}", path, cursor.unwrap());
-        json_response = send_request(client, token, &path);
-        write_to_file_or_std_out(&json_response, out_file);
-        cursor = get_cursor_from_response(&json_response);
+    first_page
+}
+
+/// The host records in a saved line: the hits of a search page written by
+/// `query`, the hits of a record written by `batch`, or a single host.
+fn saved_hosts(record: Value) -> Vec<Value> {
+    for hits in [
+        &record["result"]["hits"],
+        &record["response"]["result"]["hits"],
+    ] {
+        if let Value::Array(hits) = hits {
+            return hits.to_owned();
+        }
     }
+    vec![record]
+}
+
+fn response_cache(arg_matches: &ArgMatches) -> Option<ResponseCache> {
+    let ttl = *arg_matches
+        .get_one::<u64>("cache_ttl")
+        .expect("Argument has a default value");
+    let cache = ResponseCache::new(ResponseCache::default_dir()?)
+        .with_ttl(Duration::from_secs(ttl))
+        .with_max_bytes(cache_size_bytes(arg_matches));
+    Some(cache)
+}
+
+fn cache_size_bytes(arg_matches: &ArgMatches) -> u64 {
+    let megabytes = *arg_matches
+        .get_one::<u64>("cache_size")
+        .expect("Argument has a default value");
+    megabytes.saturating_mul(1024 * 1024)
+}
+
+fn format_megabytes(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+fn format_account(account: &Value) -> String {
+    let quota = &account["quota"];
+    let used = quota["used"].as_u64().unwrap_or(0);
+    let allowance = quota["allowance"].as_u64().unwrap_or(0);
+    format!(
+        "Login:      {}\nEmail:      {}\nQuota used: {} / {}\nRemaining:  {}\nResets at:  {}",
+        account["login"].as_str().unwrap_or("?"),
+        account["email"].as_str().unwrap_or("?"),
+        used,
+        allowance,
+        allowance.saturating_sub(used),
+        quota["resets_at"].as_str().unwrap_or("?")
+    )
 }
 
-fn write_to_file_or_std_out(json: &Value, out_file: Option<&Path>) {
+fn write_to_file_or_std_out<T: Display + ?Sized>(output: &T, out_file: Option<&Path>) {
     match out_file {
         Some(path) => {
             let mut file = fs::OpenOptions::new()
                 .create(true)
-                .write(true)
                 .append(true)
                 .open(path)
                 .expect("Unable to open file");
-            writeln!(file, "{}", json).expect("Unable to write to file");
+            writeln!(file, "{}", output).expect("Unable to write to file");
         }
-        None => println!("{}", json),
+        None => println!("{}", output),
     }
 }
 
-fn get_cursor_from_response(json_response: &Value) -> Option<String> {
-    let cursor = &json_response["result"]["links"]["next"];
-    match cursor {
-        Value::String(value) if !value.is_empty() => Some(value.to_owned()),
-        _ => None,
+fn send_request(client: &CensysClient, path: &str) -> Value {
+    send_request_with_body(client, Method::GET, path, None)
+}
+
+fn send_request_with_body(
+    client: &CensysClient,
+    method: Method,
+    path: &str,
+    body: Option<&Value>,
+) -> Value {
+    exit_unless_api_response(client.send_request(method, path, body))
+}
+
+/// Relative times resolve to a new timestamp on every run, so they can never
+/// match a cached response.
+fn exit_on_relative_time(arg_matches: &ArgMatches) {
+    let Some((_, command)) = arg_matches.subcommand() else {
+        return;
+    };
+    for name in ["at_time", "at_time_a", "at_time_b", "since", "until"] {
+        let Ok(Some(mut values)) = command.try_get_raw(name) else {
+            continue;
+        };
+        if let Some(value) = values.find(|value| value.to_string_lossy().starts_with('-')) {
+            eprintln!(
+                "offline: relative time '{}' for --{} cannot match a cached response, use an absolute time",
+                value.to_string_lossy(),
+                name
+            );
+            process::exit(1);
+        }
     }
 }
 
-fn make_path_from_query(query: &str) -> String {
-    let query = urlencoding::encode(query).into_owned();
-    format!("/hosts/search?q={}", query)
+fn exit_unless_api_response(result: Result<Value, Error>) -> Value {
+    match result {
+        Ok(json_response) => json_response,
+        // API errors are passed through so callers can report the response.
+        Err(Error::Api(_, json_response)) => json_response,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
 }
 
-fn make_hosts_path_from_cert_fingerprint(fingerprint: &str) -> String {
-    format!("/certificates/{}/hosts", fingerprint)
+/// For responses that are written out as they are: an API error is reported
+/// on stderr instead, and the command fails.
+fn exit_on_api_error(result: Result<Value, Error>) -> Value {
+    match result {
+        Err(Error::Api(_, json_response)) => {
+            eprintln!("{}", json_response);
+            process::exit(1);
+        }
+        result => exit_unless_api_response(result),
+    }
 }
 
-fn make_comments_path_from_cert_fingerprint(fingerprint: &str) -> String {
-    format!("/certificates/{}/comments", fingerprint)
+fn read_lines(source: &str) -> Vec<String> {
+    read_to_string(source)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_owned)
+        .collect()
 }
 
-fn make_path_from_ip(ip: &str) -> String {
-    format!("/hosts/{}", ip)
+fn read_to_string(source: &str) -> String {
+    if source == "-" {
+        let mut contents = String::new();
+        io::stdin()
+            .read_to_string(&mut contents)
+            .expect("Unable to read from stdin");
+        contents
+    } else {
+        fs::read_to_string(source).unwrap_or_else(|err| {
+            eprintln!("Unable to read {}: {}", source, err);
+            process::exit(1);
+        })
+    }
 }
 
-fn send_request(client: &Client, token: &str, path: &str) -> Value {
-    let response = client
-        .get(format!("{}{}", constants::BASE_URL, path))
-        .header(ACCEPT, "application/json")
-        .header(AUTHORIZATION, format!("Basic {}", token))
-        .send();
-    match response {
-        Ok(resp) => resp.json().unwrap(),
+fn get_fingerprint_or_exit(command: &ArgMatches) -> String {
+    if let Some(fingerprint) = command.get_one::<String>("fingerprint") {
+        return fingerprint.to_owned();
+    }
+    let file = command
+        .get_one::<String>("file")
+        .expect("Either fingerprint or file is required");
+    match fingerprint::hash_certificate_file(Path::new(file)) {
+        Ok(hashes) => {
+            eprintln!("SHA-256: {}", hashes.sha256);
+            eprintln!("SPKI SHA-256: {}", hashes.spki_sha256);
+            hashes.sha256
+        }
         Err(err) => {
             eprintln!("{}", err);
             process::exit(1);
@@ -224,6 +1719,12 @@ fn send_request(client: &Client, token: &str, path: &str) -> Value {
     }
 }
 
+/// The API can be pointed at another server, such as a local mock, with
+/// CENSYS_API_URL.
+fn base_url() -> String {
+    env::var("CENSYS_API_URL").unwrap_or_else(|_| constants::BASE_URL.to_owned())
+}
+
 fn get_env_or_exit(name: &str) -> String {
     env::var(name).unwrap_or_else(|_| {
         eprintln!("{} is not defined", name);
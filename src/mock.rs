@@ -0,0 +1,709 @@
+//! A local stand-in for the Censys Search API, for testing tools built on
+//! this crate without spending quota.
+//!
+//! The server answers `/hosts/search` with cursor paging, `/hosts/{ip}`,
+//! `/certificates/{fp}/hosts` and `/certificates/{fp}/comments` from canned or
+//! generated hosts, filtering searches with `query::Query`. It also keeps
+//! tags in memory and answers `/account`. It can require Basic auth, delay
+//! responses and inject faults into chosen requests.
+
+use crate::query::Query;
+use chrono::{SecondsFormat, Utc};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    io,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+use tiny_http::{Header, Request, Response, Server};
+
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 100;
+
+/// A failure the server returns instead of its normal response.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fault {
+    /// HTTP 429 with a `Retry-After` header.
+    RateLimited,
+    /// An HTTP 5xx status with a JSON error body.
+    ServerError(u16),
+    /// HTTP 200 with a truncated JSON body.
+    MalformedJson,
+}
+
+/// Returns `fault` for the `request`th request the server receives, counting
+/// from 1 and including requests that fail authentication.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FaultRule {
+    pub fault: Fault,
+    pub request: usize,
+}
+
+/// Parses `429@3`, `503@1` or `malformed@2`.
+impl FromStr for FaultRule {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid fault '{}' (expected KIND@N, e.g. 429@3, 503@1 or malformed@2)",
+                value
+            )
+        };
+        let (kind, request) = value.split_once('@').ok_or_else(invalid)?;
+        let request = request.parse::<usize>().map_err(|_| invalid())?;
+        if request == 0 {
+            return Err(invalid());
+        }
+        let fault = match kind {
+            "429" => Fault::RateLimited,
+            "malformed" => Fault::MalformedJson,
+            _ => match kind.parse::<u16>() {
+                Ok(status @ 500..=599) => Fault::ServerError(status),
+                _ => return Err(invalid()),
+            },
+        };
+        Ok(FaultRule { fault, request })
+    }
+}
+
+impl Display for FaultRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.fault {
+            Fault::RateLimited => write!(f, "429@{}", self.request),
+            Fault::ServerError(status) => write!(f, "{}@{}", status, self.request),
+            Fault::MalformedJson => write!(f, "malformed@{}", self.request),
+        }
+    }
+}
+
+pub struct MockServerBuilder {
+    hosts: Vec<Value>,
+    page_size: usize,
+    credentials: Option<(String, String)>,
+    faults: Vec<FaultRule>,
+    latency: Box<Latency>,
+    workers: usize,
+}
+
+/// Delay before answering a request, given its URL.
+type Latency = dyn Fn(&str) -> Duration + Send + Sync;
+
+impl MockServerBuilder {
+    /// Serves these host records instead of the generated ones. Each needs an
+    /// `ip`, and certificates are found through `services.certificate`.
+    pub fn hosts(mut self, hosts: Vec<Value>) -> Self {
+        self.hosts = hosts;
+        self
+    }
+
+    /// Number of hits per page when the request does not set `per_page`.
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.clamp(1, MAX_PAGE_SIZE);
+        self
+    }
+
+    /// Rejects requests without this API ID and secret as Basic auth.
+    pub fn credentials(mut self, api_id: &str, secret: &str) -> Self {
+        self.credentials = Some((api_id.to_owned(), secret.to_owned()));
+        self
+    }
+
+    pub fn fault(mut self, rule: FaultRule) -> Self {
+        self.faults.push(rule);
+        self
+    }
+
+    /// Waits `latency(url)` before answering each request, so that clients
+    /// see requests overlap and complete out of order.
+    pub fn latency<F>(mut self, latency: F) -> Self
+    where
+        F: Fn(&str) -> Duration + Send + Sync + 'static,
+    {
+        self.latency = Box::new(latency);
+        self
+    }
+
+    /// Number of threads answering requests at the same time.
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Starts serving on `address`, such as `127.0.0.1:0` for any free port.
+    pub fn start(self, address: &str) -> io::Result<MockServer> {
+        let server = Arc::new(Server::http(address).map_err(io::Error::other)?);
+        let local_address = server
+            .server_addr()
+            .to_ip()
+            .ok_or_else(|| io::Error::other("Mock server is not listening on TCP"))?;
+        let state = Arc::new(State {
+            hosts: self.hosts,
+            page_size: self.page_size,
+            token: self
+                .credentials
+                .map(|(api_id, secret)| base64::encode(format!("{}:{}", api_id, secret))),
+            faults: self.faults,
+            latency: self.latency,
+            requests: AtomicUsize::new(0),
+            in_flight: AtomicUsize::new(0),
+            peak_in_flight: AtomicUsize::new(0),
+            comments: Mutex::new(HashMap::new()),
+            next_comment_id: AtomicUsize::new(1),
+            tags: Mutex::new(Tags::default()),
+            next_tag_id: AtomicUsize::new(1),
+        });
+        let threads = (0..self.workers)
+            .map(|_| {
+                let server = Arc::clone(&server);
+                let state = Arc::clone(&state);
+                thread::spawn(move || {
+                    for request in server.incoming_requests() {
+                        state.respond(request);
+                    }
+                })
+            })
+            .collect();
+        Ok(MockServer {
+            url: format!("http://{}", local_address),
+            server,
+            state,
+            threads,
+        })
+    }
+}
+
+/// A running mock server. It stops when dropped.
+pub struct MockServer {
+    url: String,
+    server: Arc<Server>,
+    state: Arc<State>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Serves 100 generated hosts, 50 per page, without authentication.
+    pub fn builder() -> MockServerBuilder {
+        MockServerBuilder {
+            hosts: generate_hosts(100),
+            page_size: DEFAULT_PAGE_SIZE,
+            credentials: None,
+            faults: Vec::new(),
+            latency: Box::new(|_| Duration::ZERO),
+            workers: 1,
+        }
+    }
+
+    /// Base URL to pass to `CensysClient::with_base_url` or `CENSYS_API_URL`.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Number of requests received so far.
+    pub fn request_count(&self) -> usize {
+        self.state.requests.load(Ordering::SeqCst)
+    }
+
+    /// Most requests that were being answered at the same time.
+    pub fn peak_concurrency(&self) -> usize {
+        self.state.peak_in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Serves requests until the process exits.
+    pub fn join(mut self) {
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        // Each unblock releases one waiting thread.
+        for _ in &self.threads {
+            self.server.unblock();
+        }
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Addresses of generated hosts are taken from the documentation networks.
+const NETWORKS: &[&str] = &["192.0.2", "198.51.100", "203.0.113"];
+
+/// Most hosts `generate_hosts` can make, one per address in `NETWORKS`.
+pub const MAX_GENERATED_HOSTS: usize = 254 * 3;
+
+/// `count` hosts with a mix of SSH, HTTP and HTTPS services. HTTPS hosts share
+/// five certificates, so that certificate lookups find several hosts.
+///
+/// Panics if `count` exceeds `MAX_GENERATED_HOSTS`.
+pub fn generate_hosts(count: usize) -> Vec<Value> {
+    assert!(
+        count <= MAX_GENERATED_HOSTS,
+        "Cannot generate more than {} hosts",
+        MAX_GENERATED_HOSTS
+    );
+    const COUNTRIES: &[(&str, &str)] = &[
+        ("DE", "Germany"),
+        ("US", "United States"),
+        ("JP", "Japan"),
+        ("BR", "Brazil"),
+    ];
+    (0..count)
+        .map(|index| {
+            let ip = format!("{}.{}", NETWORKS[index / 254], index % 254 + 1);
+            let mut services = vec![json!({
+                "port": 22,
+                "service_name": "SSH",
+                "transport_protocol": "TCP",
+            })];
+            if index % 2 == 0 {
+                services.push(json!({
+                    "port": 80,
+                    "service_name": "HTTP",
+                    "transport_protocol": "TCP",
+                    "http": { "response": { "status_code": 200, "html_title": format!("Host {}", index) } },
+                }));
+            }
+            if index % 3 == 0 {
+                let fingerprint = certificate_fingerprint(index % 5);
+                services.push(json!({
+                    "port": 443,
+                    "service_name": "HTTP",
+                    "transport_protocol": "TCP",
+                    "certificate": fingerprint,
+                    "tls": { "certificates": { "leaf_fp_sha_256": fingerprint } },
+                }));
+            }
+            let (country_code, country) = COUNTRIES[index % COUNTRIES.len()];
+            json!({
+                "ip": ip,
+                "services": services,
+                "location": { "country": country, "country_code": country_code },
+                "autonomous_system": {
+                    "asn": 64496 + (index % 4) as u32,
+                    "name": format!("EXAMPLE-AS-{}", index % 4),
+                },
+                "dns": { "names": [format!("host{}.example.com", index)] },
+                "last_updated_at": "2023-01-05T00:00:00Z",
+            })
+        })
+        .collect()
+}
+
+/// Fingerprint of the `index`th generated certificate.
+pub fn certificate_fingerprint(index: usize) -> String {
+    Sha256::digest(format!("certificate {}", index).as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+struct State {
+    hosts: Vec<Value>,
+    page_size: usize,
+    token: Option<String>,
+    faults: Vec<FaultRule>,
+    latency: Box<Latency>,
+    requests: AtomicUsize,
+    in_flight: AtomicUsize,
+    peak_in_flight: AtomicUsize,
+    comments: Mutex<HashMap<String, Vec<Value>>>,
+    next_comment_id: AtomicUsize,
+    tags: Mutex<Tags>,
+    next_tag_id: AtomicUsize,
+}
+
+#[derive(Default)]
+struct Tags {
+    tags: Vec<Value>,
+    tagged: Vec<Tagged>,
+}
+
+/// A host or certificate carrying a tag.
+struct Tagged {
+    tag_id: String,
+    /// `hosts` or `certificates`, as in the request path.
+    kind: String,
+    /// The IP address or lowercase fingerprint.
+    target: String,
+    tagged_at: String,
+}
+
+impl State {
+    fn respond(&self, mut request: Request) {
+        let number = self.requests.fetch_add(1, Ordering::SeqCst) + 1;
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+        thread::sleep((self.latency)(request.url()));
+        let (status, body) = match self.faults.iter().find(|rule| rule.request == number) {
+            Some(rule) => fault_response(rule.fault),
+            None if !self.is_authorized(&request) => {
+                error(401, "You must authenticate with a valid API ID and secret.")
+            }
+            None => self.route(&mut request),
+        };
+        let mut response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(header("Content-Type", "application/json"));
+        if status == 429 {
+            response = response.with_header(header("Retry-After", "1"));
+        }
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        let _ = request.respond(response);
+    }
+
+    fn is_authorized(&self, request: &Request) -> bool {
+        let Some(token) = &self.token else {
+            return true;
+        };
+        request.headers().iter().any(|header| {
+            header.field.equiv("Authorization")
+                && header.value.as_str() == format!("Basic {}", token)
+        })
+    }
+
+    fn route(&self, request: &mut Request) -> (u16, String) {
+        let url = request.url().to_owned();
+        let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+        let params = parse_query(query);
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        let method = request.method().as_str().to_owned();
+        let mut body = String::new();
+        if request.as_reader().read_to_string(&mut body).is_err() {
+            return error(400, "Unreadable request body");
+        }
+        match (method.as_str(), segments.as_slice()) {
+            ("GET", ["hosts", "search"]) => self.search(&params),
+            ("GET", ["hosts", ip]) => match self.hosts.iter().find(|host| host["ip"] == *ip) {
+                Some(host) => ok(host.to_owned()),
+                None => error(404, &format!("Host {} not found", ip)),
+            },
+            ("GET", ["certificates", fingerprint, "hosts"]) => {
+                self.certificate_hosts(fingerprint, &params)
+            }
+            (_, ["certificates", fingerprint, "comments", rest @ ..]) => {
+                self.comments(&method, &fingerprint.to_lowercase(), rest, &body)
+            }
+            (_, ["tags", rest @ ..]) => self.tags(&method, rest, &body, &params),
+            (_, [kind @ ("hosts" | "certificates"), target, "tags", tag_id]) => {
+                self.tag_target(&method, kind, &target.to_lowercase(), tag_id)
+            }
+            ("GET", ["account"]) => self.account(),
+            _ => error(404, &format!("No mock for {} {}", method, path)),
+        }
+    }
+
+    fn search(&self, params: &HashMap<String, String>) -> (u16, String) {
+        let query = params.get("q").map(String::as_str).unwrap_or_default();
+        let parsed = match Query::parse(query) {
+            Ok(parsed) => parsed,
+            Err(err) => return error(400, &format!("Invalid query: {}", err)),
+        };
+        let hits: Vec<&Value> = self
+            .hosts
+            .iter()
+            .filter(|host| parsed.matches(host))
+            .collect();
+        let Some((page, next)) = self.page(&hits, params) else {
+            return error(400, "Invalid cursor");
+        };
+        ok(json!({
+            "query": query,
+            "total": hits.len(),
+            "duration": 0,
+            "total_direction": "eq",
+            "hits": page,
+            "links": { "prev": "", "next": next },
+        }))
+    }
+
+    fn certificate_hosts(
+        &self,
+        fingerprint: &str,
+        params: &HashMap<String, String>,
+    ) -> (u16, String) {
+        let fingerprint = fingerprint.to_lowercase();
+        let hosts: Vec<Value> = self
+            .hosts
+            .iter()
+            .filter(|host| {
+                host["services"].as_array().is_some_and(|services| {
+                    services
+                        .iter()
+                        .any(|service| service["certificate"] == fingerprint.as_str())
+                })
+            })
+            .map(|host| {
+                json!({
+                    "ip": host["ip"],
+                    "name": host["dns"]["names"][0],
+                    "observed_at": host["last_updated_at"],
+                    "first_observed_at": host["last_updated_at"],
+                })
+            })
+            .collect();
+        let hosts: Vec<&Value> = hosts.iter().collect();
+        let Some((page, next)) = self.page(&hosts, params) else {
+            return error(400, "Invalid cursor");
+        };
+        ok(json!({
+            "fingerprint": fingerprint,
+            "hosts": page,
+            "links": { "prev": "", "next": next },
+        }))
+    }
+
+    fn comments(
+        &self,
+        method: &str,
+        fingerprint: &str,
+        rest: &[&str],
+        body: &str,
+    ) -> (u16, String) {
+        let mut comments = self.comments.lock().expect("Comments lock poisoned");
+        let comments = comments.entry(fingerprint.to_owned()).or_default();
+        let contents = || {
+            serde_json::from_str::<Value>(body)
+                .ok()
+                .and_then(|body| body["contents"].as_str().map(str::to_owned))
+        };
+        let position = |id: &str| {
+            let id = id.parse::<u64>().ok()?;
+            comments.iter().position(|comment| comment["id"] == id)
+        };
+        match (method, rest) {
+            ("GET", []) => ok(json!({
+                "fingerprint_sha256": fingerprint,
+                "comments": comments,
+                "links": { "prev": "", "next": "" },
+            })),
+            ("POST", []) => {
+                let Some(contents) = contents() else {
+                    return error(400, "contents is required");
+                };
+                let comment = json!({
+                    "id": self.next_comment_id.fetch_add(1, Ordering::SeqCst),
+                    "fingerprint_sha256": fingerprint,
+                    "author_id": 1,
+                    "contents": contents,
+                    "created_at": Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+                });
+                comments.push(comment.clone());
+                ok(comment)
+            }
+            ("PUT", [id]) => match (position(id), contents()) {
+                (None, _) => error(404, &format!("Comment {} not found", id)),
+                (_, None) => error(400, "contents is required"),
+                (Some(index), Some(contents)) => {
+                    comments[index]["contents"] = Value::String(contents);
+                    ok(comments[index].clone())
+                }
+            },
+            ("DELETE", [id]) => match position(id) {
+                Some(index) => {
+                    comments.remove(index);
+                    ok(json!({}))
+                }
+                None => error(404, &format!("Comment {} not found", id)),
+            },
+            _ => error(405, "Method not allowed"),
+        }
+    }
+
+    fn tags(
+        &self,
+        method: &str,
+        rest: &[&str],
+        body: &str,
+        params: &HashMap<String, String>,
+    ) -> (u16, String) {
+        let mut tags = self.tags.lock().expect("Tags lock poisoned");
+        let fields = || {
+            let body = serde_json::from_str::<Value>(body).ok()?;
+            let name = body["name"].as_str()?.to_owned();
+            Some((name, body["metadata"].clone()))
+        };
+        match (method, rest) {
+            ("GET", []) => ok(json!({ "tags": tags.tags })),
+            ("POST", []) => {
+                let Some((name, metadata)) = fields() else {
+                    return error(400, "name is required");
+                };
+                let tag = json!({
+                    "id": self.next_tag_id.fetch_add(1, Ordering::SeqCst).to_string(),
+                    "name": name,
+                    "metadata": metadata,
+                    "created_at": Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+                });
+                tags.tags.push(tag.clone());
+                ok(tag)
+            }
+            (_, [id, rest @ ..]) => {
+                let Some(index) = tags.tags.iter().position(|tag| tag["id"] == *id) else {
+                    return error(404, &format!("Tag {} not found", id));
+                };
+                match (method, rest) {
+                    ("GET", []) => ok(tags.tags[index].clone()),
+                    ("PUT", []) => {
+                        let Some((name, metadata)) = fields() else {
+                            return error(400, "name is required");
+                        };
+                        tags.tags[index]["name"] = Value::String(name);
+                        tags.tags[index]["metadata"] = metadata;
+                        ok(tags.tags[index].clone())
+                    }
+                    ("DELETE", []) => {
+                        tags.tags.remove(index);
+                        tags.tagged.retain(|tagged| tagged.tag_id != *id);
+                        ok(json!({}))
+                    }
+                    ("GET", [kind @ ("hosts" | "certificates")]) => {
+                        let (list, key) = match *kind {
+                            "hosts" => ("hosts", "ip"),
+                            _ => ("certs", "fingerprint"),
+                        };
+                        let tagged: Vec<Value> = tags
+                            .tagged
+                            .iter()
+                            .filter(|tagged| tagged.tag_id == *id && tagged.kind == *kind)
+                            .map(|tagged| json!({ key: tagged.target, "tagged_at": tagged.tagged_at }))
+                            .collect();
+                        let tagged: Vec<&Value> = tagged.iter().collect();
+                        let Some((page, next)) = self.page(&tagged, params) else {
+                            return error(400, "Invalid cursor");
+                        };
+                        ok(json!({ list: page, "links": { "prev": "", "next": next } }))
+                    }
+                    _ => error(405, "Method not allowed"),
+                }
+            }
+            _ => error(405, "Method not allowed"),
+        }
+    }
+
+    /// Tags or untags a host or certificate, which need not be served.
+    fn tag_target(&self, method: &str, kind: &str, target: &str, tag_id: &str) -> (u16, String) {
+        let mut tags = self.tags.lock().expect("Tags lock poisoned");
+        if !tags.tags.iter().any(|tag| tag["id"] == tag_id) {
+            return error(404, &format!("Tag {} not found", tag_id));
+        }
+        let is_target = |tagged: &Tagged| {
+            tagged.tag_id == tag_id && tagged.kind == kind && tagged.target == target
+        };
+        match method {
+            "PUT" => {
+                if !tags.tagged.iter().any(is_target) {
+                    tags.tagged.push(Tagged {
+                        tag_id: tag_id.to_owned(),
+                        kind: kind.to_owned(),
+                        target: target.to_owned(),
+                        tagged_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+                    });
+                }
+                ok(json!({}))
+            }
+            "DELETE" => {
+                tags.tagged.retain(|tagged| !is_target(tagged));
+                ok(json!({}))
+            }
+            _ => error(405, "Method not allowed"),
+        }
+    }
+
+    /// The v1 account endpoint, which is not wrapped in a `result`. Every
+    /// request counts against the quota.
+    fn account(&self) -> (u16, String) {
+        let body = json!({
+            "email": "mock@example.com",
+            "login": "mock",
+            "quota": {
+                "used": self.requests.load(Ordering::SeqCst),
+                "allowance": 250,
+                "resets_at": "2023-02-01 00:00:00",
+            },
+        });
+        (200, body.to_string())
+    }
+
+    /// Returns the page of `items` selected by the `cursor` and `per_page`
+    /// parameters, and the cursor of the next page, or None for an unknown
+    /// cursor.
+    fn page<'a>(
+        &self,
+        items: &[&'a Value],
+        params: &HashMap<String, String>,
+    ) -> Option<(Vec<&'a Value>, String)> {
+        let offset = match params.get("cursor").filter(|cursor| !cursor.is_empty()) {
+            Some(cursor) => decode_cursor(cursor)?,
+            None => 0,
+        };
+        let page_size = params
+            .get("per_page")
+            .and_then(|per_page| per_page.parse::<usize>().ok())
+            .map_or(self.page_size, |per_page| per_page.clamp(1, MAX_PAGE_SIZE));
+        let end = (offset + page_size).min(items.len());
+        let page = items.get(offset..end).unwrap_or_default().to_vec();
+        let next = if end < items.len() {
+            base64::encode(format!("offset:{}", end))
+        } else {
+            String::new()
+        };
+        Some((page, next))
+    }
+}
+
+fn decode_cursor(cursor: &str) -> Option<usize> {
+    let decoded = String::from_utf8(base64::decode(cursor).ok()?).ok()?;
+    decoded.strip_prefix("offset:")?.parse().ok()
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter_map(|param| param.split_once('='))
+        .map(|(name, value)| {
+            let value = value.replace('+', " ");
+            let value = urlencoding::decode(&value)
+                .map(|value| value.into_owned())
+                .unwrap_or(value);
+            (name.to_owned(), value)
+        })
+        .collect()
+}
+
+fn fault_response(fault: Fault) -> (u16, String) {
+    match fault {
+        Fault::RateLimited => error(429, "Rate limit exceeded"),
+        Fault::ServerError(status) => error(status, "Injected server error"),
+        Fault::MalformedJson => (
+            200,
+            r#"{"code": 200, "status": "OK", "result": {"hits": ["#.to_owned(),
+        ),
+    }
+}
+
+fn ok(result: Value) -> (u16, String) {
+    let body = json!({ "code": 200, "status": "OK", "result": result });
+    (200, body.to_string())
+}
+
+fn error(status: u16, message: &str) -> (u16, String) {
+    let reason = tiny_http::StatusCode(status).default_reason_phrase();
+    let body = json!({ "code": status, "status": reason, "error": message });
+    (status, body.to_string())
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("Header is valid ASCII")
+}
@@ -0,0 +1,154 @@
+//! Typed records for API responses.
+//!
+//! Host records are generated from the field catalog. For certificates only
+//! the commonly used fields are spelled out. Everything else ends up in the
+//! `extra` map of the nearest record, so a record serializes back to the JSON
+//! it was read from.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// The envelope around every API response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Response<T> {
+    pub code: u16,
+    pub status: String,
+    pub result: T,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The `result` of a search, such as `/hosts/search` or `/certificates/search`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchResult<T> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    pub hits: Vec<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Links>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl<T> SearchResult<T> {
+    /// The cursor of the next page, if there is one.
+    pub fn next_cursor(&self) -> Option<&str> {
+        self.links.as_ref().and_then(Links::next_cursor)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Links {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prev: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+}
+
+impl Links {
+    /// The cursor of the next page. The API marks the last page with an
+    /// empty cursor.
+    pub fn next_cursor(&self) -> Option<&str> {
+        self.next.as_deref().filter(|next| !next.is_empty())
+    }
+}
+
+/// Host records are generated from the field catalog, see `catalog`.
+pub use catalog::{AutonomousSystem, Host, Location, Services as Service};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Certificate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fingerprint_sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fingerprint_sha1: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fingerprint_md5: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub names: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parsed: Option<ParsedCertificate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation_level: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub added_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParsedCertificate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serial_number: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issuer_dn: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject_dn: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<DistinguishedName>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<DistinguishedName>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validity_period: Option<ValidityPeriod>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DistinguishedName {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub common_name: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub organization: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidityPeriod {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub length_seconds: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Records generated by `build.rs` from `constants::CENSYS_SEARCH_FIELDS`,
+/// covering every protocol in the catalog. Field types come from the type
+/// hints in `build.rs`; fields without a hint keep the raw JSON value. The
+/// host record is also exported as `models::Host`.
+pub mod catalog {
+    use super::OneOrMany;
+    use serde::{Deserialize, Serialize};
+    use serde_json::{Map, Value};
+
+    include!(concat!(env!("OUT_DIR"), "/catalog.rs"));
+}
+
+/// A catalog record that may be either a single object or a list of them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> OneOrMany<T> {
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        match self {
+            OneOrMany::One(value) => std::slice::from_ref(value).iter(),
+            OneOrMany::Many(values) => values.iter(),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! Builders for the request paths of each API endpoint, relative to the base URL.
+
+pub fn append_query_param(path: &str, name: &str, value: &str) -> String {
+    let separator = if path.contains('?') { '&' } else { '?' };
+    format!(
+        "{}{}{}={}",
+        path,
+        separator,
+        name,
+        urlencoding::encode(value)
+    )
+}
+
+pub fn make_path_from_query(query: &str) -> String {
+    let query = urlencoding::encode(query).into_owned();
+    format!("/hosts/search?q={}", query)
+}
+
+pub fn make_aggregate_path_from_query(query: &str, field: &str, buckets: u16) -> String {
+    format!(
+        "/hosts/aggregate?q={}&field={}&num_buckets={}",
+        urlencoding::encode(query),
+        urlencoding::encode(field),
+        buckets
+    )
+}
+
+pub fn make_path_from_cert_query(query: &str, fields: &[&str]) -> String {
+    let mut path = format!("/certificates/search?q={}", urlencoding::encode(query));
+    for field in fields {
+        path = append_query_param(&path, "fields", field);
+    }
+    path
+}
+
+pub fn make_bulk_path_from_cert_fingerprints(fingerprints: &[String]) -> String {
+    let mut path = "/certificates/bulk".to_owned();
+    for fingerprint in fingerprints {
+        path = append_query_param(&path, "fingerprints", fingerprint);
+    }
+    path
+}
+
+pub fn make_path_from_cert_fingerprint(fingerprint: &str) -> String {
+    format!("/certificates/{}", fingerprint)
+}
+
+pub fn make_hosts_path_from_cert_fingerprint(fingerprint: &str) -> String {
+    format!("/certificates/{}/hosts", fingerprint)
+}
+
+pub fn make_comments_path_from_cert_fingerprint(fingerprint: &str) -> String {
+    format!("/certificates/{}/comments", fingerprint)
+}
+
+pub fn make_path_from_tag_id(tag_id: &str) -> String {
+    format!("/tags/{}", urlencoding::encode(tag_id))
+}
+
+pub fn make_tag_path_from_ip(ip: &str, tag_id: &str) -> String {
+    format!("/hosts/{}/tags/{}", ip, urlencoding::encode(tag_id))
+}
+
+pub fn make_tag_path_from_cert_fingerprint(fingerprint: &str, tag_id: &str) -> String {
+    format!(
+        "/certificates/{}/tags/{}",
+        fingerprint,
+        urlencoding::encode(tag_id)
+    )
+}
+
+pub fn make_diff_path_from_ip(
+    ip: &str,
+    ip_b: Option<&str>,
+    at_time_a: Option<&str>,
+    at_time_b: Option<&str>,
+) -> String {
+    let params: Vec<String> = [
+        ("ip_b", ip_b),
+        ("at_time", at_time_a),
+        ("at_time_b", at_time_b),
+    ]
+    .iter()
+    .filter_map(|(name, value)| {
+        value.map(|value| format!("{}={}", name, urlencoding::encode(value)))
+    })
+    .collect();
+    if params.is_empty() {
+        format!("/hosts/{}/diff", ip)
+    } else {
+        format!("/hosts/{}/diff?{}", ip, params.join("&"))
+    }
+}
+
+pub fn make_events_path_from_ip(
+    ip: &str,
+    since: Option<&str>,
+    until: Option<&str>,
+    reversed: bool,
+) -> String {
+    let mut path = format!("/experimental/hosts/{}/events", ip);
+    if let Some(since) = since {
+        path = append_query_param(&path, "start_time", since);
+    }
+    if let Some(until) = until {
+        path = append_query_param(&path, "end_time", until);
+    }
+    if reversed {
+        path = append_query_param(&path, "reversed", "true");
+    }
+    path
+}
+
+pub fn make_names_path_from_ip(ip: &str) -> String {
+    format!("/hosts/{}/names", ip)
+}
+
+pub fn make_comments_path_from_ip(ip: &str) -> String {
+    format!("/hosts/{}/comments", ip)
+}
+
+pub fn make_path_from_ip(ip: &str, at_time: Option<&str>) -> String {
+    match at_time {
+        Some(at_time) => format!("/hosts/{}?at_time={}", ip, urlencoding::encode(at_time)),
+        None => format!("/hosts/{}", ip),
+    }
+}
@@ -0,0 +1,571 @@
+//! The Censys Search query language, parsed into a `Query` that can be
+//! evaluated against saved host records or rendered back into query text.
+//!
+//! Supported syntax: `field: value`, quoted values, `*` and `?` wildcards,
+//! ranges such as `field: [80 to 443]` or `field: >1024`, bare values matched
+//! against every field, `and`, `or` and `not` (adjacent terms are joined with
+//! `and`), parentheses, and `field: (...)` groups whose terms all apply to the
+//! same element of `field`, such as `services: (port: 22 and service_name: SSH)`.
+
+use crate::constants;
+use serde_json::Value;
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display},
+    net::IpAddr,
+    ops::Bound,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query {
+    /// `*`, matching every record.
+    All,
+    /// A bare value, matched against every field of the record.
+    Text(Term),
+    /// A condition on the values at a dotted path. The path is relative to
+    /// the enclosing group, and empty for the grouped values themselves.
+    Field(String, Condition),
+    Not(Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    Equals(Term),
+    Range(Bound<Term>, Bound<Term>),
+    /// A query that a single value at the path must match as a whole.
+    Group(Box<Query>),
+}
+
+/// A value as written in the query.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Term {
+    pub text: String,
+    /// Quoted values are matched literally, without wildcards.
+    pub quoted: bool,
+}
+
+impl Query {
+    /// Parses `text`, checking every field against the catalog in
+    /// `constants::CENSYS_SEARCH_FIELDS`. An empty query matches everything.
+    pub fn parse(text: &str) -> Result<Query, String> {
+        let tokens = tokenize(text)?;
+        if tokens.is_empty() {
+            return Ok(Query::All);
+        }
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let query = parser.parse_or(&Scope::Record)?;
+        match parser.peek() {
+            None => Ok(query),
+            Some(token) => Err(format!("unexpected {}", token)),
+        }
+    }
+
+    /// Whether `record`, such as a host from a search hit, matches the query.
+    pub fn matches(&self, record: &Value) -> bool {
+        match self {
+            Query::All => true,
+            Query::Text(term) => matches_text(record, term),
+            Query::Field(field, condition) => {
+                let path: Vec<&str> = if field.is_empty() {
+                    Vec::new()
+                } else {
+                    field.split('.').collect()
+                };
+                let mut values = Vec::new();
+                collect_values(record, &path, &mut values);
+                values.iter().any(|value| condition.matches(value))
+            }
+            Query::Not(query) => !query.matches(record),
+            Query::And(left, right) => left.matches(record) && right.matches(record),
+            Query::Or(left, right) => left.matches(record) || right.matches(record),
+        }
+    }
+}
+
+impl Condition {
+    fn matches(&self, value: &Value) -> bool {
+        match self {
+            Condition::Equals(term) => matches_term(value, term),
+            Condition::Range(lower, upper) => {
+                let above = match lower {
+                    Bound::Included(bound) => compare(value, bound).is_some_and(Ordering::is_ge),
+                    Bound::Excluded(bound) => compare(value, bound).is_some_and(Ordering::is_gt),
+                    Bound::Unbounded => !value.is_null(),
+                };
+                let below = match upper {
+                    Bound::Included(bound) => compare(value, bound).is_some_and(Ordering::is_le),
+                    Bound::Excluded(bound) => compare(value, bound).is_some_and(Ordering::is_lt),
+                    Bound::Unbounded => true,
+                };
+                above && below
+            }
+            Condition::Group(query) => query.matches(value),
+        }
+    }
+}
+
+impl Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Query::All => write!(f, "*"),
+            Query::Text(term) => write!(f, "{}", term),
+            Query::Field(field, condition) if field.is_empty() => write!(f, "{}", condition),
+            Query::Field(field, condition) => write!(f, "{}: {}", field, condition),
+            Query::Not(query) => match **query {
+                Query::And(..) | Query::Or(..) => write!(f, "not ({})", query),
+                _ => write!(f, "not {}", query),
+            },
+            Query::And(left, right) => {
+                for (index, query) in [left, right].into_iter().enumerate() {
+                    if index > 0 {
+                        write!(f, " and ")?;
+                    }
+                    match **query {
+                        Query::Or(..) => write!(f, "({})", query)?,
+                        _ => write!(f, "{}", query)?,
+                    }
+                }
+                Ok(())
+            }
+            Query::Or(left, right) => write!(f, "{} or {}", left, right),
+        }
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Condition::Equals(term) => write!(f, "{}", term),
+            Condition::Range(lower, upper) => {
+                match lower {
+                    Bound::Included(term) => write!(f, "[{}", term)?,
+                    Bound::Excluded(term) => write!(f, "{{{}", term)?,
+                    Bound::Unbounded => write!(f, "[*")?,
+                }
+                match upper {
+                    Bound::Included(term) => write!(f, " to {}]", term),
+                    Bound::Excluded(term) => write!(f, " to {}}}", term),
+                    Bound::Unbounded => write!(f, " to *]"),
+                }
+            }
+            Condition::Group(query) => write!(f, "({})", query),
+        }
+    }
+}
+
+impl Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.quoted {
+            write!(
+                f,
+                "\"{}\"",
+                self.text.replace('\\', "\\\\").replace('"', "\\\"")
+            )
+        } else {
+            write!(f, "{}", self.text)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Colon,
+    Open(char),
+    Close(char),
+    Compare(&'static str),
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "'{}'", word),
+            Token::Quoted(text) => write!(f, "\"{}\"", text),
+            Token::Colon => write!(f, "':'"),
+            Token::Open(bracket) | Token::Close(bracket) => write!(f, "'{}'", bracket),
+            Token::Compare(operator) => write!(f, "'{}'", operator),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut range_depth = 0usize;
+    while let Some(&c) = chars.peek() {
+        // Values follow a colon or a comparison, or sit inside a range, and
+        // keep their colons, as in timestamps, IPv6 addresses and host:port.
+        let is_value =
+            range_depth > 0 || matches!(tokens.last(), Some(Token::Colon | Token::Compare(_)));
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | '[' | '{' => {
+                chars.next();
+                if c != '(' {
+                    range_depth += 1;
+                }
+                tokens.push(Token::Open(c));
+            }
+            ')' | ']' | '}' => {
+                chars.next();
+                if c != ')' {
+                    range_depth = range_depth.saturating_sub(1);
+                }
+                tokens.push(Token::Close(c));
+            }
+            ':' if !is_value => {
+                chars.next();
+                tokens.push(Token::Colon);
+            }
+            '<' | '>' => {
+                chars.next();
+                let operator = match (c, chars.next_if_eq(&'=').is_some()) {
+                    ('<', false) => "<",
+                    ('<', true) => "<=",
+                    ('>', false) => ">",
+                    _ => ">=",
+                };
+                tokens.push(Token::Compare(operator));
+            }
+            '"' => {
+                chars.next();
+                let mut quoted = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped) => quoted.push(escaped),
+                            None => return Err("unterminated quoted value".to_owned()),
+                        },
+                        Some(c) => quoted.push(c),
+                        None => return Err("unterminated quoted value".to_owned()),
+                    }
+                }
+                tokens.push(Token::Quoted(quoted));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    // Elsewhere a colon ends a field name.
+                    let ends_word = c.is_whitespace()
+                        || "()[]{}\"<>".contains(c)
+                        || (c == ':' && !is_value && is_field_name(&word));
+                    if ends_word {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn is_field_name(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// Where a query is being parsed: at the top level of a record, or inside a
+/// `field: (...)` group, whose fields are relative to `field` and whose bare
+/// values apply to the values of `field` themselves.
+enum Scope {
+    Record,
+    Group(String),
+}
+
+impl Scope {
+    fn resolve(&self, field: &str) -> Result<String, String> {
+        let path = match self {
+            Scope::Record => field.to_owned(),
+            Scope::Group(prefix) => format!("{}.{}", prefix, field),
+        };
+        let known = constants::CENSYS_SEARCH_FIELDS.lines().any(|known| {
+            known == path
+                || known
+                    .strip_prefix(path.as_str())
+                    .is_some_and(|rest| rest.starts_with('.'))
+        });
+        if known {
+            Ok(path)
+        } else {
+            Err(format!(
+                "unknown field '{}' (see `censys-search fields`)",
+                path
+            ))
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn parse_or(&mut self, scope: &Scope) -> Result<Query, String> {
+        let mut query = self.parse_and(scope)?;
+        while self.peek_keyword("or") {
+            self.next();
+            query = Query::Or(Box::new(query), Box::new(self.parse_and(scope)?));
+        }
+        Ok(query)
+    }
+
+    fn parse_and(&mut self, scope: &Scope) -> Result<Query, String> {
+        let mut query = self.parse_not(scope)?;
+        loop {
+            if self.peek_keyword("and") {
+                self.next();
+            } else if self.peek_keyword("or") || matches!(self.peek(), None | Some(Token::Close(_)))
+            {
+                return Ok(query);
+            }
+            query = Query::And(Box::new(query), Box::new(self.parse_not(scope)?));
+        }
+    }
+
+    fn parse_not(&mut self, scope: &Scope) -> Result<Query, String> {
+        if self.peek_keyword("not") {
+            self.next();
+            return Ok(Query::Not(Box::new(self.parse_not(scope)?)));
+        }
+        self.parse_primary(scope)
+    }
+
+    fn parse_primary(&mut self, scope: &Scope) -> Result<Query, String> {
+        match self.peek() {
+            Some(Token::Open('(')) => {
+                self.next();
+                let query = self.parse_or(scope)?;
+                self.expect_close(')')?;
+                Ok(query)
+            }
+            Some(Token::Word(word))
+                if is_field_name(word)
+                    && self.tokens.get(self.position + 1) == Some(&Token::Colon) =>
+            {
+                let field = word.to_owned();
+                let path = scope.resolve(&field)?;
+                self.position += 2;
+                Ok(Query::Field(field, self.parse_condition(path)?))
+            }
+            Some(Token::Open(_) | Token::Compare(_)) if matches!(scope, Scope::Group(_)) => {
+                let Scope::Group(path) = scope else {
+                    unreachable!("Scope was just matched");
+                };
+                Ok(Query::Field(
+                    String::new(),
+                    self.parse_condition(path.to_owned())?,
+                ))
+            }
+            _ => {
+                let term = self.parse_term()?;
+                Ok(match scope {
+                    _ if term.text == "*" && !term.quoted => Query::All,
+                    Scope::Record => Query::Text(term),
+                    Scope::Group(_) => Query::Field(String::new(), Condition::Equals(term)),
+                })
+            }
+        }
+    }
+
+    /// Parses what follows `field:`, where `path` is the field's full path.
+    fn parse_condition(&mut self, path: String) -> Result<Condition, String> {
+        match self.peek() {
+            Some(Token::Open('(')) => {
+                self.next();
+                let query = self.parse_or(&Scope::Group(path))?;
+                self.expect_close(')')?;
+                Ok(Condition::Group(Box::new(query)))
+            }
+            Some(Token::Open(open)) => {
+                let inclusive = *open == '[';
+                self.next();
+                let lower = bound(self.parse_term()?, inclusive);
+                if !self.peek_keyword("to") {
+                    return Err("expected 'to' in range".to_owned());
+                }
+                self.next();
+                let upper = self.parse_term()?;
+                let inclusive = match self.next() {
+                    Some(Token::Close(']')) => true,
+                    Some(Token::Close('}')) => false,
+                    _ => return Err("expected ']' or '}' to close range".to_owned()),
+                };
+                Ok(Condition::Range(lower, bound(upper, inclusive)))
+            }
+            Some(Token::Compare(operator)) => {
+                let operator = *operator;
+                self.next();
+                let term = self.parse_term()?;
+                Ok(match operator {
+                    ">" => Condition::Range(Bound::Excluded(term), Bound::Unbounded),
+                    ">=" => Condition::Range(Bound::Included(term), Bound::Unbounded),
+                    "<" => Condition::Range(Bound::Unbounded, Bound::Excluded(term)),
+                    _ => Condition::Range(Bound::Unbounded, Bound::Included(term)),
+                })
+            }
+            _ => Ok(Condition::Equals(self.parse_term()?)),
+        }
+    }
+
+    fn parse_term(&mut self) -> Result<Term, String> {
+        match self.next() {
+            Some(Token::Word(text)) => Ok(Term {
+                text,
+                quoted: false,
+            }),
+            Some(Token::Quoted(text)) => Ok(Term { text, quoted: true }),
+            Some(token) => Err(format!("expected a value, found {}", token)),
+            None => Err("expected a value, found the end of the query".to_owned()),
+        }
+    }
+
+    fn expect_close(&mut self, close: char) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Close(found)) if found == close => Ok(()),
+            _ => Err(format!("expected '{}'", close)),
+        }
+    }
+}
+
+fn bound(term: Term, inclusive: bool) -> Bound<Term> {
+    if term.text == "*" && !term.quoted {
+        Bound::Unbounded
+    } else if inclusive {
+        Bound::Included(term)
+    } else {
+        Bound::Excluded(term)
+    }
+}
+
+/// Collects the values at `path`, looking inside every element of the lists
+/// along the way.
+fn collect_values<'a>(value: &'a Value, path: &[&str], values: &mut Vec<&'a Value>) {
+    match (value, path.split_first()) {
+        (Value::Array(elements), _) => {
+            for element in elements {
+                collect_values(element, path, values);
+            }
+        }
+        (_, None) => values.push(value),
+        (Value::Object(object), Some((field, rest))) => {
+            if let Some(child) = object.get(*field) {
+                collect_values(child, rest, values);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Field values match whole values, case-insensitively, or wildcard patterns.
+/// An unquoted `*` matches any value that is present.
+fn matches_term(value: &Value, term: &Term) -> bool {
+    if !term.quoted && term.text == "*" {
+        return !value.is_null();
+    }
+    let Some(text) = leaf_text(value) else {
+        return false;
+    };
+    if !term.quoted && term.text.contains(['*', '?']) {
+        return matches_wildcard(&text.to_lowercase(), &term.text.to_lowercase());
+    }
+    match value {
+        Value::Number(number) => term
+            .text
+            .parse::<f64>()
+            .is_ok_and(|expected| number.as_f64() == Some(expected)),
+        _ => text.eq_ignore_ascii_case(&term.text),
+    }
+}
+
+/// Bare values match any field containing them, case-insensitively, or any
+/// field matching them as a wildcard pattern.
+fn matches_text(value: &Value, term: &Term) -> bool {
+    match value {
+        Value::Array(elements) => elements.iter().any(|element| matches_text(element, term)),
+        Value::Object(object) => object.values().any(|child| matches_text(child, term)),
+        Value::String(text) if term.quoted || !term.text.contains(['*', '?']) => {
+            text.to_lowercase().contains(&term.text.to_lowercase())
+        }
+        value => matches_term(value, term),
+    }
+}
+
+fn leaf_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.to_owned()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(flag.to_string()),
+        _ => None,
+    }
+}
+
+/// Compares numbers numerically, IP addresses by address and everything else
+/// as text, which orders RFC 3339 timestamps correctly.
+fn compare(value: &Value, bound: &Term) -> Option<Ordering> {
+    match value {
+        Value::Number(number) => number
+            .as_f64()?
+            .partial_cmp(&bound.text.parse::<f64>().ok()?),
+        Value::String(text) => match (text.parse::<IpAddr>(), bound.text.parse::<IpAddr>()) {
+            (Ok(address), Ok(bound)) => Some(address.cmp(&bound)),
+            _ => Some(text.as_str().cmp(bound.text.as_str())),
+        },
+        _ => None,
+    }
+}
+
+/// Matches `text` against a pattern where `*` matches any run of characters
+/// and `?` any single character.
+fn matches_wildcard(text: &str, pattern: &str) -> bool {
+    let text: Vec<char> = text.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    let (mut t, mut p) = (0, 0);
+    // Where the last `*` was seen in the pattern, and the text position it
+    // was tried at, so that it can be extended on a mismatch.
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            t += 1;
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
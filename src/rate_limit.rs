@@ -0,0 +1,34 @@
+use std::{
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Spaces out requests from any number of threads so that no more than the
+/// configured number start per second.
+pub struct RateLimiter {
+    interval: Option<Duration>,
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(per_second: Option<f64>) -> Self {
+        RateLimiter {
+            interval: per_second.map(|per_second| Duration::from_secs_f64(1.0 / per_second)),
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn wait(&self) {
+        let Some(interval) = self.interval else {
+            return;
+        };
+        let slot = {
+            let mut next_slot = self.next_slot.lock().expect("Rate limiter lock poisoned");
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + interval;
+            slot
+        };
+        thread::sleep(slot.saturating_duration_since(Instant::now()));
+    }
+}
@@ -0,0 +1,213 @@
+//! `--format sqlite`: hosts from search pages and host lookups, upserted into
+//! normalized tables so that repeated runs build up a history.
+//!
+//! Every run is a row in `runs`, holding the command and the query, DNS name,
+//! ASN or address it was given, and `observations` records the page of each
+//! run that each host was seen on. The other tables hold the latest known
+//! state, with the first and last run that saw each row.
+
+use chrono::{SecondsFormat, Utc};
+use rusqlite::{params, Connection, Transaction};
+use serde_json::Value;
+use std::path::Path;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    command TEXT NOT NULL,
+    query TEXT NOT NULL,
+    started_at TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS observations (
+    run_id INTEGER NOT NULL REFERENCES runs (id),
+    page INTEGER NOT NULL,
+    ip TEXT NOT NULL,
+    PRIMARY KEY (run_id, ip)
+);
+CREATE TABLE IF NOT EXISTS hosts (
+    ip TEXT PRIMARY KEY,
+    asn INTEGER,
+    as_name TEXT,
+    country_code TEXT,
+    city TEXT,
+    last_updated_at TEXT,
+    record TEXT NOT NULL,
+    first_run INTEGER NOT NULL REFERENCES runs (id),
+    last_run INTEGER NOT NULL REFERENCES runs (id)
+);
+CREATE TABLE IF NOT EXISTS services (
+    ip TEXT NOT NULL REFERENCES hosts (ip),
+    port INTEGER NOT NULL,
+    transport_protocol TEXT NOT NULL,
+    service_name TEXT,
+    extended_service_name TEXT,
+    certificate TEXT,
+    first_run INTEGER NOT NULL REFERENCES runs (id),
+    last_run INTEGER NOT NULL REFERENCES runs (id),
+    PRIMARY KEY (ip, port, transport_protocol)
+);
+CREATE TABLE IF NOT EXISTS names (
+    ip TEXT NOT NULL REFERENCES hosts (ip),
+    name TEXT NOT NULL,
+    first_run INTEGER NOT NULL REFERENCES runs (id),
+    last_run INTEGER NOT NULL REFERENCES runs (id),
+    PRIMARY KEY (ip, name)
+);
+CREATE TABLE IF NOT EXISTS certificates (
+    fingerprint_sha256 TEXT PRIMARY KEY,
+    subject_dn TEXT,
+    issuer_dn TEXT,
+    first_run INTEGER NOT NULL REFERENCES runs (id),
+    last_run INTEGER NOT NULL REFERENCES runs (id)
+);
+";
+
+pub struct Database {
+    connection: Connection,
+}
+
+impl Database {
+    /// Opens or creates the database at `path`, adding any missing tables.
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Database { connection })
+    }
+
+    /// Records the start of a run of `command` for `query`, returning its ID.
+    pub fn start_run(&self, command: &str, query: &str) -> rusqlite::Result<i64> {
+        self.connection.execute(
+            "INSERT INTO runs (command, query, started_at) VALUES (?1, ?2, ?3)",
+            params![
+                command,
+                query,
+                Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+            ],
+        )?;
+        Ok(self.connection.last_insert_rowid())
+    }
+
+    /// Upserts the hosts in a search page or host lookup response, returning
+    /// how many there were. Responses without hosts, such as API errors, are
+    /// skipped.
+    pub fn insert_page(
+        &mut self,
+        run_id: i64,
+        page: usize,
+        json_response: &Value,
+    ) -> rusqlite::Result<usize> {
+        let hosts = hosts_in(json_response);
+        let transaction = self.connection.transaction()?;
+        for host in &hosts {
+            insert_host(&transaction, run_id, page, host)?;
+        }
+        transaction.commit()?;
+        Ok(hosts.len())
+    }
+}
+
+fn hosts_in(json_response: &Value) -> Vec<&Value> {
+    let result = &json_response["result"];
+    match &result["hits"] {
+        Value::Array(hits) => hits.iter().collect(),
+        _ if result["ip"].is_string() => vec![result],
+        _ => Vec::new(),
+    }
+}
+
+fn insert_host(
+    transaction: &Transaction,
+    run_id: i64,
+    page: usize,
+    host: &Value,
+) -> rusqlite::Result<()> {
+    let Some(ip) = host["ip"].as_str() else {
+        return Ok(());
+    };
+    transaction.execute(
+        "INSERT INTO hosts (ip, asn, as_name, country_code, city, last_updated_at, record, first_run, last_run)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)
+         ON CONFLICT (ip) DO UPDATE SET
+             asn = excluded.asn,
+             as_name = excluded.as_name,
+             country_code = excluded.country_code,
+             city = excluded.city,
+             last_updated_at = excluded.last_updated_at,
+             record = excluded.record,
+             last_run = excluded.last_run",
+        params![
+            ip,
+            host["autonomous_system"]["asn"].as_i64(),
+            host["autonomous_system"]["name"].as_str(),
+            host["location"]["country_code"].as_str(),
+            host["location"]["city"].as_str(),
+            host["last_updated_at"].as_str(),
+            host.to_string(),
+            run_id,
+        ],
+    )?;
+    transaction.execute(
+        "INSERT OR REPLACE INTO observations (run_id, page, ip) VALUES (?1, ?2, ?3)",
+        params![run_id, page, ip],
+    )?;
+
+    for service in host["services"].as_array().into_iter().flatten() {
+        let Some(port) = service["port"].as_u64() else {
+            continue;
+        };
+        let certificate = service["certificate"]
+            .as_str()
+            .or_else(|| service["tls"]["certificates"]["leaf_fp_sha_256"].as_str());
+        transaction.execute(
+            "INSERT INTO services (ip, port, transport_protocol, service_name, extended_service_name, certificate, first_run, last_run)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)
+             ON CONFLICT (ip, port, transport_protocol) DO UPDATE SET
+                 service_name = excluded.service_name,
+                 extended_service_name = excluded.extended_service_name,
+                 certificate = excluded.certificate,
+                 last_run = excluded.last_run",
+            params![
+                ip,
+                port,
+                service["transport_protocol"].as_str().unwrap_or_default(),
+                service["service_name"].as_str(),
+                service["extended_service_name"].as_str(),
+                certificate,
+                run_id,
+            ],
+        )?;
+        if let Some(fingerprint) = certificate {
+            let leaf_data = &service["tls"]["certificates"]["leaf_data"];
+            // Services without TLS details keep whatever an earlier run stored.
+            transaction.execute(
+                "INSERT INTO certificates (fingerprint_sha256, subject_dn, issuer_dn, first_run, last_run)
+                 VALUES (?1, ?2, ?3, ?4, ?4)
+                 ON CONFLICT (fingerprint_sha256) DO UPDATE SET
+                     subject_dn = COALESCE(excluded.subject_dn, certificates.subject_dn),
+                     issuer_dn = COALESCE(excluded.issuer_dn, certificates.issuer_dn),
+                     last_run = excluded.last_run",
+                params![
+                    fingerprint,
+                    leaf_data["subject_dn"].as_str(),
+                    leaf_data["issuer_dn"].as_str(),
+                    run_id,
+                ],
+            )?;
+        }
+    }
+
+    let names = [&host["dns"]["names"], &host["dns"]["reverse_dns"]["names"]];
+    for name in names
+        .into_iter()
+        .filter_map(Value::as_array)
+        .flatten()
+        .filter_map(Value::as_str)
+    {
+        transaction.execute(
+            "INSERT INTO names (ip, name, first_run, last_run) VALUES (?1, ?2, ?3, ?3)
+             ON CONFLICT (ip, name) DO UPDATE SET last_run = excluded.last_run",
+            params![ip, name, run_id],
+        )?;
+    }
+    Ok(())
+}
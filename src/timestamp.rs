@@ -0,0 +1,106 @@
+use chrono::{DateTime, Duration, NaiveDate, SecondsFormat, Utc};
+
+/// Parses an RFC 3339 timestamp, a plain `YYYY-MM-DD` date (midnight UTC) or a
+/// relative offset into the past such as `-30d`, returning an RFC 3339 UTC string.
+pub fn parse_timestamp(value: &str) -> Result<String, String> {
+    let timestamp = if let Some(offset) = value.strip_prefix('-') {
+        Utc::now()
+            .checked_sub_signed(parse_offset(offset)?)
+            .ok_or_else(|| format!("relative time '{}' is too far in the past", value))?
+    } else if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        date.and_hms_opt(0, 0, 0)
+            .expect("Midnight is a valid time")
+            .and_utc()
+    } else {
+        DateTime::parse_from_rfc3339(value)
+            .map_err(|err| format!("invalid RFC 3339 timestamp '{}': {}", value, err))?
+            .with_timezone(&Utc)
+    };
+    if timestamp > Utc::now() {
+        return Err(format!("timestamp '{}' is in the future", value));
+    }
+    Ok(timestamp.to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+fn parse_offset(offset: &str) -> Result<Duration, String> {
+    let invalid = || {
+        format!(
+            "invalid relative time '-{}' (expected e.g. -90m, -12h, -30d, -2w)",
+            offset
+        )
+    };
+    let (unit_index, _) = offset.char_indices().last().ok_or_else(invalid)?;
+    let (amount, unit) = offset.split_at(unit_index);
+    let amount = amount.parse::<i64>().map_err(|_| invalid())?;
+    let duration = match unit {
+        "s" => Duration::try_seconds(amount),
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => None,
+    };
+    duration.ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_timestamp;
+    use chrono::{DateTime, Duration, Utc};
+
+    #[test]
+    fn absolute_timestamps() {
+        assert_eq!(
+            parse_timestamp("2023-01-05T10:00:00+02:00").unwrap(),
+            "2023-01-05T08:00:00Z"
+        );
+        assert_eq!(
+            parse_timestamp("2023-01-05").unwrap(),
+            "2023-01-05T00:00:00Z"
+        );
+        assert!(parse_timestamp("2023-01-05 10:00")
+            .unwrap_err()
+            .starts_with("invalid RFC 3339 timestamp '2023-01-05 10:00'"));
+    }
+
+    #[test]
+    fn relative_timestamps() {
+        let parsed = parse_timestamp("-30d").unwrap();
+        let timestamp = DateTime::parse_from_rfc3339(&parsed).unwrap();
+        let expected = Utc::now() - Duration::days(30);
+        assert!(
+            (expected - timestamp.with_timezone(&Utc))
+                .num_seconds()
+                .abs()
+                <= 5
+        );
+        for value in ["-30", "-d", "-30y", "-"] {
+            assert!(
+                parse_timestamp(value)
+                    .unwrap_err()
+                    .starts_with("invalid relative time"),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn future_and_overflowing_timestamps_are_rejected() {
+        assert_eq!(
+            parse_timestamp("2999-01-01").unwrap_err(),
+            "timestamp '2999-01-01' is in the future"
+        );
+        assert_eq!(
+            parse_timestamp("--5d").unwrap_err(),
+            "timestamp '--5d' is in the future"
+        );
+        assert_eq!(
+            parse_timestamp("-100000000w").unwrap_err(),
+            "relative time '-100000000w' is too far in the past"
+        );
+        assert!(parse_timestamp(&format!("-{}w", i64::MAX))
+            .unwrap_err()
+            .starts_with("invalid relative time"));
+    }
+}
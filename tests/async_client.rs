@@ -0,0 +1,36 @@
+#![cfg(feature = "async")]
+
+use censys_search::{asynchronous::CensysClient, mock, Error};
+use common::start_mock_server;
+use futures_util::{pin_mut, StreamExt};
+
+mod common;
+
+#[tokio::test]
+async fn search_streams_hits_across_pages_lazily() {
+    let server = start_mock_server(mock::generate_hosts(3), 2);
+    let client = CensysClient::new("id", "secret").with_base_url(server.url());
+    let hits = client.search_hosts("services.port: 22");
+    pin_mut!(hits);
+
+    let first = hits.next().await.unwrap().unwrap();
+    assert_eq!(first["ip"], "192.0.2.1");
+    assert_eq!(server.request_count(), 1);
+
+    let rest: Vec<_> = hits
+        .map(|hit| hit.unwrap()["ip"].to_owned())
+        .collect()
+        .await;
+    assert_eq!(rest, ["192.0.2.2", "192.0.2.3"]);
+    assert_eq!(server.request_count(), 2);
+}
+
+#[tokio::test]
+async fn api_errors_are_returned() {
+    let server = start_mock_server(mock::generate_hosts(3), 2);
+    let client = CensysClient::new("id", "secret").with_base_url(server.url());
+    match client.view_host("192.0.2.9", None).await {
+        Err(Error::Api(404, body)) => assert_eq!(body["error"], "Host 192.0.2.9 not found"),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
@@ -0,0 +1,90 @@
+use censys_search::{blocking::CensysClient, mock, Error};
+use common::{mock_server, start_mock_server};
+
+mod common;
+
+#[test]
+fn hits_follow_cursors_lazily() {
+    let server = start_mock_server(mock::generate_hosts(3), 2);
+    let client = CensysClient::new("id", "secret").with_base_url(server.url());
+    let mut hits = client.search("services.port: 22").hits();
+
+    assert_eq!(hits.next().unwrap().unwrap()["ip"], "192.0.2.1");
+    assert_eq!(server.request_count(), 1);
+
+    let rest: Vec<_> = hits.map(|hit| hit.unwrap()["ip"].to_owned()).collect();
+    assert_eq!(rest, ["192.0.2.2", "192.0.2.3"]);
+    assert_eq!(server.request_count(), 2);
+}
+
+#[test]
+fn max_results_stops_paging() {
+    let server = start_mock_server(mock::generate_hosts(3), 2);
+    let client = CensysClient::new("id", "secret").with_base_url(server.url());
+
+    let hits: Vec<_> = client
+        .search("services.port: 22")
+        .max_results(2)
+        .hits()
+        .map(|hit| hit.unwrap()["ip"].to_owned())
+        .collect();
+    assert_eq!(hits, ["192.0.2.1", "192.0.2.2"]);
+    assert_eq!(server.request_count(), 1);
+
+    let pages: Vec<_> = client
+        .search("services.port: 22")
+        .max_results(1)
+        .pages()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(pages.len(), 1);
+    assert_eq!(pages[0]["result"]["hits"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn errors_end_the_iteration() {
+    let server = mock_server(mock::generate_hosts(3), 2)
+        .fault("500@2".parse().unwrap())
+        .start("127.0.0.1:0")
+        .unwrap();
+    let client = CensysClient::new("id", "secret").with_base_url(server.url());
+    let results: Vec<_> = client.search("services.port: 22").hits().collect();
+    assert_eq!(results.len(), 3);
+    assert!(results[..2].iter().all(Result::is_ok));
+    match &results[2] {
+        Err(Error::Api(500, body)) => assert_eq!(body["error"], "Injected server error"),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn hosts_are_typed() {
+    let server = start_mock_server(mock::generate_hosts(3), 2);
+    let client = CensysClient::new("id", "secret").with_base_url(server.url());
+    let ips: Vec<_> = client
+        .search("services.port: 22")
+        .hosts()
+        .map(|host| host.unwrap().ip)
+        .collect();
+    assert_eq!(ips, ["192.0.2.1", "192.0.2.2", "192.0.2.3"]);
+}
+
+#[test]
+fn account_url_follows_the_base_url() {
+    let client = CensysClient::new("id", "secret");
+    assert_eq!(
+        client.account_url(),
+        "https://search.censys.io/api/v1/account"
+    );
+    let client = client.with_base_url("https://proxy.example.com/censys/api/v2/");
+    assert_eq!(
+        client.account_url(),
+        "https://proxy.example.com/censys/api/v1/account"
+    );
+
+    let server = start_mock_server(mock::generate_hosts(3), 2);
+    let client = CensysClient::new("id", "secret").with_base_url(server.url());
+    assert_eq!(client.account_url(), format!("{}/account", server.url()));
+    assert!(client.account().is_ok());
+    assert_eq!(server.request_count(), 1);
+}
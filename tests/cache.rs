@@ -0,0 +1,330 @@
+use censys_search::{
+    cache::{normalize_url, ResponseCache},
+    mock,
+};
+use common::start_mock_server;
+use serde_json::json;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    thread,
+    time::Duration,
+};
+
+mod common;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "censys-search-cache-{}-{}",
+        std::process::id(),
+        name
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn run(url: &str, cache_dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_censys-search"))
+        .env("CENSYS_API_URL", url)
+        .env("CENSYS_CACHE_DIR", cache_dir)
+        .args(["--api_id", "id", "--secret", "secret"])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn repeated_lookups_are_answered_from_the_cache() {
+    let server = start_mock_server(mock::generate_hosts(2), 1);
+    let url = server.url();
+    let cache_dir = temp_dir("lookups");
+
+    let first = run(url, &cache_dir, &["ip", "192.0.2.1"]);
+    let second = run(url, &cache_dir, &["ip", "192.0.2.1"]);
+    assert!(first.status.success() && second.status.success());
+    assert_eq!(first.stdout, second.stdout);
+    assert_eq!(server.request_count(), 1);
+
+    run(url, &cache_dir, &["--refresh", "ip", "192.0.2.1"]);
+    run(url, &cache_dir, &["--no_cache", "ip", "192.0.2.1"]);
+    assert_eq!(server.request_count(), 3);
+
+    let stats = run(url, &cache_dir, &["cache", "stats"]);
+    assert!(String::from_utf8_lossy(&stats.stdout).contains("Entries:   1 (0 expired)"));
+    let clear = run(url, &cache_dir, &["cache", "clear"]);
+    assert_eq!(
+        String::from_utf8_lossy(&clear.stdout).trim(),
+        "Removed 1 cached responses"
+    );
+    fs::remove_dir_all(cache_dir).unwrap();
+}
+
+#[test]
+fn offline_runs_replay_cached_pages() {
+    let server = start_mock_server(mock::generate_hosts(2), 1);
+    let url = server.url();
+    let cache_dir = temp_dir("offline");
+
+    let online = run(url, &cache_dir, &["query", "services.port: 22"]);
+    assert_eq!(String::from_utf8_lossy(&online.stdout).lines().count(), 2);
+    assert_eq!(server.request_count(), 2);
+
+    let offline = Command::new(env!("CARGO_BIN_EXE_censys-search"))
+        .env("CENSYS_API_URL", url)
+        .env("CENSYS_CACHE_DIR", &cache_dir)
+        .env_remove("CENSYS_API_ID")
+        .env_remove("CENSYS_SECRET")
+        .args([
+            "--offline",
+            "--cache_ttl",
+            "0",
+            "query",
+            "services.port: 22",
+        ])
+        .output()
+        .unwrap();
+    assert!(offline.status.success());
+    assert_eq!(offline.stdout, online.stdout);
+    assert_eq!(server.request_count(), 2);
+
+    let relative = run(
+        url,
+        &cache_dir,
+        &["--offline", "ip", "192.0.2.1", "--at_time", "-30d"],
+    );
+    assert_eq!(relative.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&relative.stderr)
+        .contains("offline: relative time '-30d' for --at_time cannot match a cached response"));
+
+    let miss = run(url, &cache_dir, &["--offline", "ip", "192.0.2.9"]);
+    assert_eq!(miss.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&miss.stderr).contains("offline: no cached response for"));
+    assert_eq!(server.request_count(), 2);
+    fs::remove_dir_all(cache_dir).unwrap();
+}
+
+#[test]
+fn expired_entries_are_not_returned_and_are_pruned() {
+    let dir = temp_dir("ttl");
+    let cache = ResponseCache::new(&dir).with_ttl(Duration::ZERO);
+    cache.insert(
+        "https://example.com/hosts/192.0.2.1",
+        &json!({"ip": "192.0.2.1"}),
+    );
+    assert_eq!(cache.get("https://example.com/hosts/192.0.2.1"), None);
+    assert_eq!(cache.stats().unwrap().expired, 1);
+    assert_eq!(cache.prune().unwrap(), 1);
+    assert_eq!(cache.stats().unwrap().entries, 0);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn least_recently_used_entries_are_evicted_first() {
+    let dir = temp_dir("lru");
+    let response = json!({"padding": "x".repeat(100)});
+    let entry_size = {
+        let cache = ResponseCache::new(&dir);
+        cache.insert("probe", &response);
+        let size = cache.stats().unwrap().bytes;
+        cache.clear().unwrap();
+        size
+    };
+    let cache = ResponseCache::new(&dir).with_max_bytes(entry_size * 2 + entry_size / 2);
+    cache.insert("first", &response);
+    thread::sleep(Duration::from_millis(20));
+    cache.insert("second", &response);
+    thread::sleep(Duration::from_millis(20));
+    assert!(cache.get("first").is_some());
+    thread::sleep(Duration::from_millis(20));
+    cache.insert("third", &response);
+
+    let cached: Vec<_> = ["first", "second", "third"]
+        .into_iter()
+        .filter(|url| cache.get(url).is_some())
+        .collect();
+    assert_eq!(cached, ["first", "third"]);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn eviction_goes_by_file_metadata() {
+    let dir = temp_dir("metadata");
+    fs::create_dir_all(&dir).unwrap();
+    // Eviction does not read entries, so it also removes files it could not
+    // parse, oldest first.
+    fs::write(dir.join("unreadable.json"), "x".repeat(1000)).unwrap();
+    thread::sleep(Duration::from_millis(20));
+    let cache = ResponseCache::new(&dir).with_max_bytes(500);
+    cache.insert("first", &json!({"ip": "192.0.2.1"}));
+    assert!(!dir.join("unreadable.json").exists());
+    assert!(cache.get("first").is_some());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn inserts_scan_the_directory_only_once_over_the_cap() {
+    let dir = temp_dir("running-size");
+    let cache = ResponseCache::new(&dir).with_max_bytes(2500);
+    cache.insert("first", &json!({ "padding": "x".repeat(100) }));
+    // Files the cache did not write itself are only noticed by the next
+    // scan, which runs once its own inserts take it over the cap.
+    fs::write(dir.join("other.json"), "x".repeat(5000)).unwrap();
+    cache.insert("second", &json!({ "padding": "x".repeat(100) }));
+    assert!(dir.join("other.json").exists());
+    cache.insert("third", &json!({ "padding": "x".repeat(2300) }));
+    assert!(!dir.join("other.json").exists());
+    assert!(cache.get("third").is_some());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn concurrent_inserts_never_expose_partial_entries() {
+    let dir = temp_dir("concurrent");
+    let cache = ResponseCache::new(&dir);
+    cache.insert("shared", &json!({ "writer": 0 }));
+    thread::scope(|scope| {
+        for writer in 1..=8 {
+            let cache = &cache;
+            scope.spawn(move || {
+                for _ in 0..100 {
+                    let response = json!({ "writer": writer, "padding": "x".repeat(50_000) });
+                    cache.insert("shared", &response);
+                    assert!(cache.get("shared").is_some());
+                }
+            });
+        }
+    });
+    assert!(cache.get("shared").is_some());
+    assert_eq!(cache.stats().unwrap().entries, 1);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn invalidation_stops_at_path_segments() {
+    let dir = temp_dir("invalidate");
+    let cache = ResponseCache::new(&dir);
+    let urls = [
+        "https://example.com/hosts/192.0.2.1",
+        "https://example.com/hosts/192.0.2.1?at_time=2023-01-01T00%3A00%3A00Z",
+        "https://example.com/hosts/192.0.2.1/comments",
+        "https://example.com/hosts/192.0.2.10",
+        "https://example.com/hosts/192.0.2.1.example.com",
+    ];
+    for url in urls {
+        cache.insert(url, &json!({}));
+    }
+    let removed = cache
+        .invalidate(&["https://example.com/hosts/192.0.2.1".to_owned()])
+        .unwrap();
+    assert_eq!(removed, 3);
+    let cached: Vec<_> = urls
+        .into_iter()
+        .filter(|url| cache.get(url).is_some())
+        .collect();
+    assert_eq!(
+        cached,
+        [
+            "https://example.com/hosts/192.0.2.10",
+            "https://example.com/hosts/192.0.2.1.example.com"
+        ]
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn tag_changes_invalidate_tag_listings() {
+    let server = start_mock_server(mock::generate_hosts(2), 1);
+    let url = server.url();
+    let cache_dir = temp_dir("tags");
+    let created = run(url, &cache_dir, &["tags", "create", "perimeter"]);
+    assert!(created.status.success());
+    let listings: [&[&str]; 4] = [
+        &["tags", "list"],
+        &["tags", "hosts", "1"],
+        &["tags", "certs", "1"],
+        &["ip", "192.0.2.1"],
+    ];
+    let fingerprint = mock::certificate_fingerprint(0);
+    let changes: [&[&str]; 5] = [
+        &["tags", "create", "edge"],
+        &["tags", "update", "1", "--name", "internal"],
+        &["tags", "delete", "2", "--yes"],
+        &["tags", "tag", "1", "--ip", "192.0.2.1"],
+        &["tags", "tag", "1", "--cert", &fingerprint],
+    ];
+    for change in changes {
+        for listing in listings {
+            run(url, &cache_dir, listing);
+        }
+        let cached = server.request_count();
+        for listing in listings {
+            run(url, &cache_dir, listing);
+        }
+        assert_eq!(server.request_count(), cached, "{:?}", change);
+
+        let output = run(url, &cache_dir, change);
+        assert!(output.status.success(), "{:?}", change);
+        let changed = server.request_count();
+        for listing in &listings[..3] {
+            run(url, &cache_dir, listing);
+        }
+        assert_eq!(server.request_count(), changed + 3, "{:?}", change);
+    }
+    // The listings show the changes rather than what was cached before them.
+    let list = run(url, &cache_dir, &["tags", "list"]);
+    let list = String::from_utf8_lossy(&list.stdout);
+    assert!(list.contains("internal") && !list.contains("edge"));
+    let hosts = run(url, &cache_dir, &["tags", "hosts", "1"]);
+    assert!(String::from_utf8_lossy(&hosts.stdout).contains("192.0.2.1"));
+    let certs = run(url, &cache_dir, &["tags", "certs", "1"]);
+    assert!(String::from_utf8_lossy(&certs.stdout).contains(&fingerprint));
+
+    // Only tagging the host itself changes its view.
+    let before = server.request_count();
+    run(url, &cache_dir, &["ip", "192.0.2.1"]);
+    assert_eq!(server.request_count(), before);
+    run(
+        url,
+        &cache_dir,
+        &["tags", "untag", "1", "--ip", "192.0.2.1"],
+    );
+    let before = server.request_count();
+    run(url, &cache_dir, &["ip", "192.0.2.1"]);
+    assert_eq!(server.request_count(), before + 1);
+    let hosts = run(url, &cache_dir, &["tags", "hosts", "1"]);
+    assert!(!String::from_utf8_lossy(&hosts.stdout).contains("192.0.2.1"));
+    fs::remove_dir_all(cache_dir).unwrap();
+}
+
+#[test]
+fn failed_tag_changes_exit_with_an_error() {
+    let server = start_mock_server(mock::generate_hosts(2), 1);
+    let cache_dir = temp_dir("tag-errors");
+    let changes: [&[&str]; 4] = [
+        &["tags", "delete", "42", "--yes"],
+        &["tags", "update", "42", "--name", "edge"],
+        &["tags", "tag", "42", "--ip", "192.0.2.1"],
+        &["tags", "untag", "42", "--ip", "192.0.2.1"],
+    ];
+    for change in changes {
+        let output = run(server.url(), &cache_dir, change);
+        assert_eq!(output.status.code(), Some(1), "{:?}", change);
+        assert!(output.stdout.is_empty(), "{:?}", change);
+        assert!(
+            String::from_utf8_lossy(&output.stderr).contains("Tag 42 not found"),
+            "{:?}",
+            change
+        );
+    }
+    let _ = fs::remove_dir_all(cache_dir);
+}
+
+#[test]
+fn query_parameter_order_does_not_matter() {
+    assert_eq!(
+        normalize_url("/hosts/search?q=port%3A22&per_page=100&cursor=abc"),
+        normalize_url("/hosts/search?cursor=abc&q=port%3A22&per_page=100")
+    );
+    assert_eq!(normalize_url("/hosts/192.0.2.1?"), "/hosts/192.0.2.1");
+}
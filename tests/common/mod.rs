@@ -0,0 +1,17 @@
+//! The mock server the integration tests run against.
+
+use censys_search::mock::{MockServer, MockServerBuilder};
+use serde_json::Value;
+
+/// A mock server over `hosts`, `page_size` hits to a page, for tests that
+/// configure it further before starting it.
+pub fn mock_server(hosts: Vec<Value>, page_size: usize) -> MockServerBuilder {
+    MockServer::builder().hosts(hosts).page_size(page_size)
+}
+
+/// Starts a mock server over `hosts` on any free local port.
+// Not every test crate that includes this module needs a plain server.
+#[allow(dead_code)]
+pub fn start_mock_server(hosts: Vec<Value>, page_size: usize) -> MockServer {
+    mock_server(hosts, page_size).start("127.0.0.1:0").unwrap()
+}
@@ -0,0 +1,84 @@
+use censys_search::mock::{self, MockServer};
+use serde_json::Value;
+use std::{
+    fs,
+    process::{Command, Output},
+    time::Duration,
+};
+
+mod common;
+
+/// Serves generated hosts from worker threads, answering later addresses in
+/// the input faster than earlier ones so that completion order differs from
+/// input order. There is no host at `192.0.2.255`.
+fn start_batch_server() -> MockServer {
+    common::mock_server(mock::generate_hosts(8), mock::DEFAULT_PAGE_SIZE)
+        .workers(16)
+        .latency(|url| {
+            let last_octet: u64 = url.rsplit('.').next().unwrap().parse().unwrap();
+            Duration::from_millis(200u64.saturating_sub(last_octet * 20))
+        })
+        .start("127.0.0.1:0")
+        .unwrap()
+}
+
+fn run_ip_batch(server: &MockServer, addresses: &[&str], concurrency: &str) -> Output {
+    let input = std::env::temp_dir().join(format!(
+        "censys-search-concurrency-{}-{}.txt",
+        std::process::id(),
+        concurrency
+    ));
+    fs::write(&input, addresses.join("\n")).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_censys-search"))
+        .env("CENSYS_API_URL", server.url())
+        .args(["--api_id", "id", "--secret", "secret", "--no_cache"])
+        .args(["--concurrency", concurrency])
+        .args(["ip", "--input", input.to_str().unwrap()])
+        .output()
+        .unwrap();
+    fs::remove_file(input).unwrap();
+    output
+}
+
+fn output_inputs(output: &Output) -> Vec<String> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| {
+            let record: Value = serde_json::from_str(line).unwrap();
+            assert_eq!(record["input"], record["response"]["result"]["ip"]);
+            record["input"].as_str().unwrap().to_owned()
+        })
+        .collect()
+}
+
+#[test]
+fn output_follows_input_order() {
+    let server = start_batch_server();
+    let addresses = [
+        "192.0.2.1",
+        "192.0.2.2",
+        "192.0.2.3",
+        "192.0.2.4",
+        "192.0.2.5",
+        "192.0.2.6",
+        "192.0.2.7",
+        "192.0.2.8",
+    ];
+    let output = run_ip_batch(&server, &addresses, "4");
+    assert!(output.status.success());
+    assert_eq!(output_inputs(&output), addresses);
+    let peak = server.peak_concurrency();
+    assert!(peak > 1 && peak <= 4, "peak in flight was {}", peak);
+}
+
+#[test]
+fn failures_are_reported_without_aborting() {
+    let server = start_batch_server();
+    let addresses = ["192.0.2.1", "192.0.2.255", "192.0.2.3"];
+    let output = run_ip_batch(&server, &addresses, "2");
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(output_inputs(&output), ["192.0.2.1", "192.0.2.3"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("1 of 3 inputs failed"));
+    assert!(stderr.contains("192.0.2.255: HTTP 404: Host 192.0.2.255 not found"));
+}
@@ -0,0 +1,301 @@
+#![cfg(feature = "mock")]
+
+use censys_search::{
+    blocking::CensysClient,
+    mock::{self, Fault, FaultRule, MockServer},
+    Error,
+};
+use reqwest::Method;
+use serde_json::json;
+use std::{
+    io::{BufRead, BufReader},
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+fn client(server: &MockServer) -> CensysClient {
+    CensysClient::new("id", "secret").with_base_url(server.url())
+}
+
+#[test]
+fn search_pages_through_every_matching_host() {
+    let server = MockServer::builder()
+        .page_size(10)
+        .start("127.0.0.1:0")
+        .unwrap();
+    let ips: Vec<String> = client(&server)
+        .search("services.port: 443")
+        .hosts()
+        .map(|host| host.unwrap().ip)
+        .collect();
+    // Every third of the 100 generated hosts serves HTTPS.
+    assert_eq!(ips.len(), 34);
+    assert_eq!(ips[0], "192.0.2.1");
+    assert_eq!(server.request_count(), 4);
+
+    let hits = client(&server).search("*").max_results(25).hits().count();
+    assert_eq!(hits, 25);
+}
+
+#[test]
+fn searches_use_the_query_language() {
+    let server = MockServer::builder()
+        .hosts(mock::generate_hosts(12))
+        .start("127.0.0.1:0")
+        .unwrap();
+    let count = |query: &str| client(&server).search(query).hits().count();
+    // Even hosts serve HTTP, every third HTTPS and every fourth is in Germany.
+    assert_eq!(
+        count("services.port: [80 to 443] and not location.country_code: DE"),
+        5
+    );
+    assert_eq!(
+        count("services.port: 22 and services.service_name: HTTP"),
+        8
+    );
+    assert_eq!(count("services: (port: 22 and service_name: HTTP)"), 0);
+    assert_eq!(count("services: (port: 443 and service_name: HTTP)"), 4);
+    assert_eq!(count("dns.names: host1*.example.com"), 3);
+
+    let pages: Vec<_> = client(&server).search("services.port: (").pages().collect();
+    match pages.as_slice() {
+        [Err(Error::Api(400, body))] => {
+            assert!(body["error"].as_str().unwrap().starts_with("Invalid query"))
+        }
+        other => panic!("expected a 400, got {:?}", other),
+    }
+}
+
+#[test]
+fn host_and_certificate_lookups() {
+    let server = MockServer::builder().start("127.0.0.1:0").unwrap();
+    let client = client(&server);
+
+    let host = client
+        .send_request(Method::GET, "/hosts/192.0.2.4", None)
+        .unwrap();
+    assert_eq!(host["result"]["dns"]["names"][0], "host3.example.com");
+    match client.send_request(Method::GET, "/hosts/192.0.2.254", None) {
+        Err(Error::Api(404, _)) => {}
+        other => panic!("expected a 404, got {:?}", other),
+    }
+
+    let fingerprint = mock::certificate_fingerprint(0);
+    let hosts = client
+        .send_request(
+            Method::GET,
+            &format!("/certificates/{}/hosts", fingerprint),
+            None,
+        )
+        .unwrap();
+    // Hosts 0, 15, 30, ... share the first certificate.
+    assert_eq!(hosts["result"]["hosts"].as_array().unwrap().len(), 7);
+    assert_eq!(hosts["result"]["hosts"][1]["ip"], "192.0.2.16");
+}
+
+#[test]
+fn certificate_comments_are_stored() {
+    let server = MockServer::builder().start("127.0.0.1:0").unwrap();
+    let client = client(&server);
+    let path = format!(
+        "/certificates/{}/comments",
+        mock::certificate_fingerprint(1)
+    );
+
+    let added = client
+        .send_request(
+            Method::POST,
+            &path,
+            Some(&json!({ "contents": "Lookalike domain" })),
+        )
+        .unwrap();
+    let id = added["result"]["id"].as_u64().unwrap();
+    client
+        .send_request(
+            Method::PUT,
+            &format!("{}/{}", path, id),
+            Some(&json!({ "contents": "Lookalike domain, reported" })),
+        )
+        .unwrap();
+    let listed = client.send_request(Method::GET, &path, None).unwrap();
+    assert_eq!(
+        listed["result"]["comments"][0]["contents"],
+        "Lookalike domain, reported"
+    );
+
+    client
+        .send_request(Method::DELETE, &format!("{}/{}", path, id), None)
+        .unwrap();
+    let listed = client.send_request(Method::GET, &path, None).unwrap();
+    assert_eq!(listed["result"]["comments"], json!([]));
+}
+
+#[test]
+fn tags_are_stored() {
+    let server = MockServer::builder().start("127.0.0.1:0").unwrap();
+    let client = client(&server);
+
+    let created = client
+        .send_request(
+            Method::POST,
+            "/tags",
+            Some(&json!({ "name": "perimeter", "metadata": {} })),
+        )
+        .unwrap();
+    let id = created["result"]["id"].as_str().unwrap().to_owned();
+    let fingerprint = mock::certificate_fingerprint(2);
+    for path in [
+        format!("/hosts/192.0.2.1/tags/{}", id),
+        format!("/certificates/{}/tags/{}", fingerprint, id),
+    ] {
+        client.send_request(Method::PUT, &path, None).unwrap();
+    }
+    let hosts = client
+        .send_request(Method::GET, &format!("/tags/{}/hosts", id), None)
+        .unwrap();
+    assert_eq!(hosts["result"]["hosts"][0]["ip"], "192.0.2.1");
+    let certs = client
+        .send_request(Method::GET, &format!("/tags/{}/certificates", id), None)
+        .unwrap();
+    assert_eq!(certs["result"]["certs"][0]["fingerprint"], fingerprint);
+
+    client
+        .send_request(Method::DELETE, &format!("/tags/{}", id), None)
+        .unwrap();
+    let listed = client.send_request(Method::GET, "/tags", None).unwrap();
+    assert_eq!(listed["result"]["tags"], json!([]));
+    match client.send_request(Method::PUT, &format!("/hosts/192.0.2.1/tags/{}", id), None) {
+        Err(Error::Api(404, _)) => {}
+        other => panic!("expected a 404, got {:?}", other),
+    }
+}
+
+#[test]
+fn latency_and_workers_overlap_requests() {
+    let server = MockServer::builder()
+        .workers(4)
+        .latency(|_| Duration::from_millis(100))
+        .start("127.0.0.1:0")
+        .unwrap();
+    let threads: Vec<_> = (1..=4)
+        .map(|host| {
+            let url = server.url().to_owned();
+            thread::spawn(move || {
+                CensysClient::new("id", "secret")
+                    .with_base_url(&url)
+                    .send_request(Method::GET, &format!("/hosts/192.0.2.{}", host), None)
+                    .unwrap();
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert!(server.peak_concurrency() > 1);
+    assert!(client(&server).account().is_ok());
+}
+
+#[test]
+fn basic_auth_is_checked() {
+    let server = MockServer::builder()
+        .credentials("id", "secret")
+        .start("127.0.0.1:0")
+        .unwrap();
+    assert!(client(&server)
+        .send_request(Method::GET, "/hosts/192.0.2.1", None)
+        .is_ok());
+
+    let wrong = CensysClient::new("id", "wrong").with_base_url(server.url());
+    match wrong.send_request(Method::GET, "/hosts/192.0.2.1", None) {
+        Err(Error::Api(401, _)) => {}
+        other => panic!("expected a 401, got {:?}", other),
+    }
+}
+
+#[test]
+fn faults_are_injected_into_chosen_requests() {
+    let server = MockServer::builder()
+        .page_size(10)
+        .fault("429@2".parse().unwrap())
+        .fault("503@3".parse().unwrap())
+        .fault("malformed@4".parse().unwrap())
+        .start("127.0.0.1:0")
+        .unwrap();
+    let client = client(&server);
+
+    let pages: Vec<_> = client.search("*").pages().collect();
+    assert_eq!(pages.len(), 2);
+    assert!(pages[0].is_ok());
+    assert!(matches!(pages[1], Err(Error::Api(429, _))));
+
+    let result = client.send_request(Method::GET, "/hosts/192.0.2.1", None);
+    assert!(matches!(result, Err(Error::Api(503, _))));
+    let result = client.send_request(Method::GET, "/hosts/192.0.2.1", None);
+    assert!(matches!(result, Err(Error::InvalidJson(200, _))));
+    assert!(client
+        .send_request(Method::GET, "/hosts/192.0.2.1", None)
+        .is_ok());
+}
+
+#[test]
+fn fault_rules_parse() {
+    assert_eq!(
+        "malformed@2".parse::<FaultRule>(),
+        Ok(FaultRule {
+            fault: Fault::MalformedJson,
+            request: 2
+        })
+    );
+    assert_eq!(
+        "502@1".parse::<FaultRule>().unwrap().fault,
+        Fault::ServerError(502)
+    );
+    for invalid in ["404@1", "429", "429@0", "slow@1"] {
+        assert!(invalid.parse::<FaultRule>().is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn cli_runs_against_the_mock_binary() {
+    let mut mock = Command::new(env!("CARGO_BIN_EXE_censys-mock"))
+        .args([
+            "--listen",
+            "127.0.0.1:0",
+            "--hosts",
+            "30",
+            "--page_size",
+            "4",
+        ])
+        .args(["--api_id", "id", "--secret", "secret"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut url = String::new();
+    BufReader::new(mock.stdout.take().unwrap())
+        .read_line(&mut url)
+        .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_censys-search"))
+        .env("CENSYS_API_URL", url.trim())
+        .args(["--api_id", "id", "--secret", "secret", "--no_cache"])
+        .args(["query", "location.country_code: DE"])
+        .output()
+        .unwrap();
+    mock.kill().unwrap();
+    mock.wait().unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    // Hosts 0, 4, ..., 28 are in Germany, four to a page.
+    let pages: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(pages.len(), 2);
+    assert_eq!(pages[0]["result"]["total"], 8);
+}
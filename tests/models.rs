@@ -0,0 +1,130 @@
+use censys_search::models::{catalog, Certificate, Host, OneOrMany, Response, SearchResult};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+
+fn load_fixture(name: &str) -> Value {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+}
+
+/// Reads a fixture into `T` and checks that it serializes back unchanged.
+fn round_trip<T: DeserializeOwned + Serialize>(name: &str) -> T {
+    let fixture = load_fixture(name);
+    let typed: T = serde_json::from_value(fixture.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&typed).unwrap(), fixture);
+    typed
+}
+
+#[test]
+fn host_search_round_trips() {
+    let response: Response<SearchResult<Host>> = round_trip("host_search.json");
+    let result = &response.result;
+    assert_eq!(result.total, Some(1830412));
+    assert_eq!(
+        result.next_cursor(),
+        Some("eyJBZnRlciI6WyIxOTIuMC4yLjEwIl19")
+    );
+    assert_eq!(result.extra["total_is_estimate"], false);
+
+    let host = &result.hits[0];
+    assert_eq!(host.ip, "192.0.2.10");
+    assert_eq!(host.autonomous_system.as_ref().unwrap().asn, 64500);
+    let location = host.location.as_ref().unwrap();
+    assert_eq!(location.coordinates.as_ref().unwrap().latitude, 50.1155);
+    assert_eq!(location.registered_country_code.as_deref(), Some("DE"));
+    assert!(host.extra.contains_key("matched_services"));
+}
+
+#[test]
+fn host_round_trips() {
+    let response: Response<Host> = round_trip("host.json");
+    let host = &response.result;
+    let services = host.services.as_ref().unwrap();
+    assert_eq!(services.len(), 2);
+
+    let ssh = services[0].ssh.as_ref().unwrap();
+    assert_eq!(
+        ssh.endpoint_id
+            .as_ref()
+            .unwrap()
+            .software_version
+            .as_deref(),
+        Some("OpenSSH_8.9p1")
+    );
+    assert_eq!(
+        ssh.hassh_fingerprint,
+        Some(json!("f555226df1963d1d3c09daf865abdc9a"))
+    );
+    assert_eq!(services[0].extra["_decoded"], "ssh");
+
+    let http = services[1].http.as_ref().unwrap();
+    assert_eq!(http.response.as_ref().unwrap().status_code, Some(200));
+    let tls = services[1].tls.as_ref().unwrap();
+    assert_eq!(
+        tls.certificates.as_ref().unwrap().leaf_fp_sha_256,
+        services[1].certificate
+    );
+
+    let dns = host.dns.as_ref().unwrap();
+    assert_eq!(
+        dns.reverse_dns
+            .as_ref()
+            .unwrap()
+            .names
+            .as_ref()
+            .unwrap()
+            .len(),
+        1
+    );
+    assert!(dns.extra.contains_key("records"));
+
+    // Key and value pairs in the catalog are objects in responses.
+    let other = host
+        .operating_system
+        .as_ref()
+        .unwrap()
+        .other
+        .as_ref()
+        .unwrap();
+    assert_eq!(other["family"], "Linux");
+    assert_eq!(
+        host.last_updated_at.as_deref(),
+        Some("2023-01-05T02:11:09.103Z")
+    );
+}
+
+#[test]
+fn certificate_round_trips() {
+    let response: Response<Certificate> = round_trip("certificate.json");
+    let certificate = &response.result;
+    let parsed = certificate.parsed.as_ref().unwrap();
+    assert_eq!(parsed.subject_dn.as_deref(), Some("CN=www.example.com"));
+    assert_eq!(
+        parsed.validity_period.as_ref().unwrap().length_seconds,
+        Some(7775999)
+    );
+    assert!(parsed.extra.contains_key("extensions"));
+    assert!(certificate.extra.contains_key("ct"));
+}
+
+#[test]
+fn catalog_records_cover_every_protocol() {
+    let service: catalog::Services = serde_json::from_value(json!({
+        "port": 502,
+        "service_name": "MODBUS",
+        "modbus": {
+            "function": 43,
+            "mei_response": {
+                "conformity_level": 1,
+                "objects": [{"vendor": "Schneider Electric", "revision": "v2.6"}]
+            }
+        }
+    }))
+    .unwrap();
+    let modbus = service.modbus.unwrap();
+    let objects = modbus.mei_response.unwrap().objects.unwrap();
+    assert!(matches!(objects, OneOrMany::Many(_)));
+    let vendors: Vec<_> = objects.iter().map(|object| &object.vendor).collect();
+    assert_eq!(vendors, [&Some(json!("Schneider Electric"))]);
+}
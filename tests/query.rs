@@ -0,0 +1,179 @@
+use censys_search::query::Query;
+use serde_json::{json, Value};
+use std::fs;
+
+fn host() -> Value {
+    let path = format!("{}/tests/fixtures/host.json", env!("CARGO_MANIFEST_DIR"));
+    let response: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    response["result"].to_owned()
+}
+
+fn matches(query: &str, record: &Value) -> bool {
+    Query::parse(query)
+        .unwrap_or_else(|err| panic!("{}: {}", query, err))
+        .matches(record)
+}
+
+#[test]
+fn field_values_wildcards_and_ranges() {
+    let host = json!({
+        "ip": "192.0.2.10",
+        "services": [
+            { "port": 22, "service_name": "SSH" },
+            { "port": 8443, "service_name": "HTTP", "http": { "response": { "html_title": "Router Login" } } },
+        ],
+        "location": { "country_code": "DE" },
+        "dns": { "names": ["mail.example.com", "www.example.com"] },
+        "last_updated_at": "2023-01-05T10:00:00Z",
+    });
+    for query in [
+        "services.port: 22",
+        "services.service_name: ssh",
+        "location.country_code: \"DE\"",
+        "dns.names: www.example.com",
+        "dns.names: *.example.com",
+        "dns.names: ma?l.example.com",
+        "services.http.response.html_title: \"router login\"",
+        "services.port: [8000 to 9000]",
+        "services.port: {21 to 22]",
+        "services.port: >=8443",
+        "ip: [192.0.2.0 to 192.0.2.255]",
+        "services.http: *",
+        "router",
+        "*",
+        "",
+    ] {
+        assert!(matches(query, &host), "{}", query);
+    }
+    for query in [
+        "services.port: 23",
+        "dns.names: *.example.net",
+        "services.port: {8443 to *]",
+        "services.port: <22",
+        "ip: [192.0.2.100 to 192.0.2.255]",
+        "location.city: *",
+        "nginx",
+    ] {
+        assert!(!matches(query, &host), "{}", query);
+    }
+}
+
+#[test]
+fn boolean_operators_and_groups() {
+    let host = json!({
+        "services": [
+            { "port": 22, "service_name": "SSH" },
+            { "port": 80, "service_name": "HTTP" },
+        ],
+        "location": { "country_code": "DE" },
+    });
+    for query in [
+        "services.port: 22 and location.country_code: DE",
+        "services.port: 22 location.country_code: DE",
+        "services.port: 23 or services.port: 80",
+        "not services.port: 443",
+        "NOT (services.port: 443 OR location.country_code: US)",
+        "services.port: (443 or 80)",
+        "services: (port: 22 and service_name: SSH)",
+        "services: (port: [1 to 100] and not service_name: FTP)",
+    ] {
+        assert!(matches(query, &host), "{}", query);
+    }
+    for query in [
+        "services.port: 22 and location.country_code: US",
+        "not services.port: 22",
+        // Both conditions hold, but on different services.
+        "services: (port: 22 and service_name: HTTP)",
+    ] {
+        assert!(!matches(query, &host), "{}", query);
+    }
+}
+
+#[test]
+fn saved_fixture_is_searchable() {
+    let host = host();
+    assert!(matches("autonomous_system.asn: 64500", &host));
+    assert!(matches(
+        "services: (port: 443 and service_name: HTTP)",
+        &host
+    ));
+    assert!(!matches("location.country_code: FR", &host));
+}
+
+#[test]
+fn values_keep_their_colons() {
+    let host = json!({
+        "ip": "fe80::1",
+        "dns": { "names": ["host1.example.com:80"] },
+    });
+    for query in [
+        "ip: fe80::1",
+        "ip: fe80::1 and dns.names: host1.example.com:80",
+        "ip:fe80::1",
+        "ip: ::1 or ip: fe80::*",
+        "ip: [fe80::0 to fe80::ff]",
+        "dns.names: host1.example.com:80",
+        "dns.names: *.example.com:80",
+    ] {
+        assert!(matches(query, &host), "{}", query);
+    }
+    for query in ["ip: ::1", "dns.names: host1.example.com:443"] {
+        assert!(!matches(query, &host), "{}", query);
+    }
+    assert_eq!(
+        Query::parse("ip: fe80::1 dns.names: host1.example.com:80")
+            .unwrap()
+            .to_string(),
+        "ip: fe80::1 and dns.names: host1.example.com:80"
+    );
+}
+
+#[test]
+fn unknown_fields_and_bad_syntax_are_rejected() {
+    assert_eq!(
+        Query::parse("services.prot: 22").unwrap_err(),
+        "unknown field 'services.prot' (see `censys-search fields`)"
+    );
+    assert!(Query::parse("services: (prot: 22)")
+        .unwrap_err()
+        .contains("'services.prot'"));
+    for query in [
+        "services.port:",
+        "(services.port: 22",
+        "services.port: [1 2]",
+        "services.port: [1 to 2",
+        "\"unterminated",
+        "services.port: 22)",
+    ] {
+        assert!(Query::parse(query).is_err(), "{}", query);
+    }
+}
+
+#[test]
+fn queries_render_back_to_query_text() {
+    for (query, rendered) in [
+        ("services.port:22", "services.port: 22"),
+        ("a.b.c", "a.b.c"),
+        (
+            "services.port: 22 location.country: \"United States\"",
+            "services.port: 22 and location.country: \"United States\"",
+        ),
+        (
+            "(services.port: 22 OR services.port: 80) AND NOT dns.names: *.example.com",
+            "(services.port: 22 or services.port: 80) and not dns.names: *.example.com",
+        ),
+        ("services.port: >1024", "services.port: {1024 to *]"),
+        (
+            "services: (port: [1 to 1024} and service_name: SSH)",
+            "services: (port: [1 to 1024} and service_name: SSH)",
+        ),
+        (
+            "dns.reverse_dns.resolved_at: [2023-01-01T00:00:00Z to *]",
+            "dns.reverse_dns.resolved_at: [2023-01-01T00:00:00Z to *]",
+        ),
+    ] {
+        let parsed = Query::parse(query).unwrap();
+        assert_eq!(parsed.to_string(), rendered);
+        assert_eq!(Query::parse(rendered).unwrap(), parsed);
+    }
+}